@@ -0,0 +1,26 @@
+//! Helpers shared between the benchmarks.
+
+use five_words::{get_words, Options, Word};
+
+/// Returns a list of up to `count` pseudorandom words of five different
+/// letters, fewer if some of them turn out to be anagrams. A simple xorshift
+/// generator is enough here, and it keeps the list the same between runs.
+pub fn random_words(count: usize) -> Vec<Word> {
+    let mut state: u32 = 0x2545_f491;
+    let mut input = String::new();
+    while input.len() < count * 6 {
+        let mut letters = Vec::with_capacity(5);
+        while letters.len() < 5 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let letter = b'a' + (state % 26) as u8;
+            if !letters.contains(&letter) {
+                letters.push(letter);
+            }
+        }
+        input.push_str(std::str::from_utf8(&letters).unwrap());
+        input.push('\n');
+    }
+    get_words(input.as_bytes(), &Options::default()).unwrap()
+}
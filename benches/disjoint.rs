@@ -0,0 +1,42 @@
+//! Compares the two ways of checking whether two words have any letters in
+//! common: comparing their masks, and walking their sorted letters.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+mod common;
+
+const WORD_COUNT: usize = 2000;
+
+fn bench_disjoint(c: &mut Criterion) {
+    let words = common::random_words(WORD_COUNT);
+    let pairs = (words.len() * words.len()) as u64;
+
+    let mut group = c.benchmark_group("is_disjoint_with");
+    group.throughput(Throughput::Elements(pairs));
+    group.bench_function("mask", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for a in words.iter() {
+                for b in words.iter() {
+                    count += black_box(a).is_disjoint_with(black_box(b)) as usize;
+                }
+            }
+            count
+        })
+    });
+    group.bench_function("sorted", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for a in words.iter() {
+                for b in words.iter() {
+                    count += black_box(a).is_disjoint_with_sorted(black_box(b)) as usize;
+                }
+            }
+            count
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_disjoint);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Compares the ways in which the search can be divided into parallel tasks.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use five_words::{get_disjoint_indices, NoProgress, Options, Strategy};
+
+mod common;
+
+const WORD_COUNT: usize = 400;
+
+fn bench_strategy(c: &mut Criterion) {
+    let words = common::random_words(WORD_COUNT);
+
+    let mut group = c.benchmark_group("get_disjoint_indices");
+    group.sample_size(10);
+    for (name, strategy) in [("words", Strategy::Words), ("pairs", Strategy::Pairs)] {
+        let options = Options {
+            strategy,
+            ..Options::default()
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| get_disjoint_indices(&words, 5, &options, &NoProgress))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_strategy);
+criterion_main!(benches);
@@ -0,0 +1,193 @@
+//! Saving a word list that has been read in a compact binary form, so that it
+//! can be loaded again without parsing the word lists.
+//!
+//! A cache file starts with a magic number and a key that describes what the
+//! word list was read from, followed by the number of words and then, for
+//! each word, its mask, the letters as the search numbers them, its lists, its
+//! key, its spelling and its anagrams. Numbers are little-endian, and strings
+//! are a 32-bit length followed by the bytes.
+
+use crate::{letters_of, Word};
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// What every cache file starts with, which changes along with the format.
+const MAGIC: &[u8; 8] = b"5WORDS\x00\x03";
+
+/// Writes the word list to the cache, along with the key, which should tell
+/// apart everything that the word list depends on, such as the files it was
+/// read from and the options it was read with.
+pub fn write_word_cache<W: Write>(
+    mut out: W,
+    key: &str,
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    out.write_all(MAGIC)?;
+    write_bytes(&mut out, key.as_bytes())?;
+    write_u32(&mut out, count(word_list.len())?)?;
+    for word in word_list {
+        write_u32(&mut out, word.mask)?;
+        // The letters are only renumbered when some are excluded, which the
+        // key tells but the cache doesn't know how to read.
+        write_u32(&mut out, word.bits)?;
+        write_u32(&mut out, word.sources)?;
+        // The key is only the sorted letters when anagrams are grouped,
+        // which isn't something the mask can tell.
+        write_bytes(&mut out, &word.key)?;
+        write_bytes(&mut out, word.original_word.as_bytes())?;
+        write_u32(&mut out, count(word.anagrams.len())?)?;
+        for anagram in &word.anagrams {
+            write_bytes(&mut out, anagram.as_bytes())?;
+        }
+    }
+    out.flush()
+}
+
+/// Reads a word list that `write_word_cache` wrote. Returns `None` if the
+/// cache was written by another version, or with another key.
+pub fn read_word_cache<R: Read>(mut input: R, key: &str) -> std::io::Result<Option<Vec<Word>>> {
+    let mut magic = [0; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC || read_bytes(&mut input)? != key.as_bytes() {
+        return Ok(None);
+    }
+    let len = read_u32(&mut input)?;
+    let mut word_list = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        let mask = read_u32(&mut input)?;
+        let bits = read_u32(&mut input)?;
+        let sources = read_u32(&mut input)?;
+        let key = read_bytes(&mut input)?;
+        let original_word = read_string(&mut input)?;
+        // The sorted letters are the letters of the mask, so they're not
+        // stored, but they do have to be the letters of the spelling.
+        let spelled = original_word
+            .bytes()
+            .fold(0, |mask, c| mask | 1 << (c.wrapping_sub(b'a') & 31));
+        if spelled != mask
+            || original_word.len() != mask.count_ones() as usize
+            || bits.count_ones() != mask.count_ones()
+        {
+            return Err(invalid_data(format!(
+                "the cache has {} with the letters {}",
+                original_word,
+                letters_of(mask)
+            )));
+        }
+        let mut word = Word::new(letters_of(mask).into_bytes().into(), original_word);
+        word.key = key.into();
+        word.bits = bits;
+        word.sources = sources;
+        for _ in 0..read_u32(&mut input)? {
+            word.anagrams.push(read_string(&mut input)?);
+        }
+        word_list.push(word);
+    }
+    Ok(Some(word_list))
+}
+
+fn count(len: usize) -> std::io::Result<u32> {
+    u32::try_from(len).map_err(|_| invalid_data(String::from("too many entries for the cache")))
+}
+
+fn write_u32<W: Write>(out: &mut W, n: u32) -> std::io::Result<()> {
+    out.write_all(&n.to_le_bytes())
+}
+
+fn write_bytes<W: Write>(out: &mut W, bytes: &[u8]) -> std::io::Result<()> {
+    write_u32(out, count(bytes.len())?)?;
+    out.write_all(bytes)
+}
+
+fn read_u32<R: Read>(input: &mut R) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_bytes<R: Read>(input: &mut R) -> std::io::Result<Vec<u8>> {
+    let len = read_u32(input)?;
+    let mut bytes = vec![];
+    input.take(len.into()).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    Ok(bytes)
+}
+
+fn read_string<R: Read>(input: &mut R) -> std::io::Result<String> {
+    String::from_utf8(read_bytes(input)?)
+        .map_err(|_| invalid_data(String::from("the cache has a word that isn't UTF-8")))
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_words_from_lists, Options};
+
+    const KEY: &str = "fjord.txt gucks.txt";
+
+    fn word_list(options: &Options) -> Vec<Word> {
+        let lists = [
+            "fjord\ngucks\nnymph\nvibex\nwaltz\nfrodj\n",
+            "gucks\nwaltz\nbrick\nglent\njumpy\nvozhd\n",
+        ];
+        get_words_from_lists(lists.iter().map(|list| list.as_bytes()), options).unwrap()
+    }
+
+    fn write(word_list: &[Word]) -> Vec<u8> {
+        let mut cache = vec![];
+        write_word_cache(&mut cache, KEY, word_list).unwrap();
+        cache
+    }
+
+    fn assert_same(read: &[Word], written: &[Word]) {
+        assert_eq!(read, written);
+        for (read, written) in read.iter().zip(written) {
+            assert_eq!(read.original_word, written.original_word);
+            assert_eq!(read.word, written.word);
+            assert_eq!(read.mask, written.mask);
+            assert_eq!(read.bits, written.bits);
+            assert_eq!(read.sources, written.sources);
+            assert_eq!(read.anagrams, written.anagrams);
+        }
+    }
+
+    #[test]
+    fn word_list_round_trips() {
+        let written = word_list(&Options::default());
+        assert!(written.iter().any(|word| word.sources == 0b11));
+        assert!(written.iter().any(|word| !word.anagrams.is_empty()));
+        let read = read_word_cache(&write(&written)[..], KEY).unwrap().unwrap();
+        assert_same(&read, &written);
+    }
+
+    #[test]
+    fn renumbered_word_list_round_trips() {
+        let options = Options {
+            excluded_letters: 1 << (b'q' - b'a') | 1 << (b'x' - b'a'),
+            ..Options::default()
+        };
+        let written = word_list(&options);
+        assert!(written.iter().any(|word| word.bits != word.mask));
+        let read = read_word_cache(&write(&written)[..], KEY).unwrap().unwrap();
+        assert_same(&read, &written);
+    }
+
+    #[test]
+    fn other_version_is_not_read() {
+        let mut cache = write(&word_list(&Options::default()));
+        cache[MAGIC.len() - 1] ^= 0xff;
+        assert!(read_word_cache(&cache[..], KEY).unwrap().is_none());
+    }
+
+    #[test]
+    fn other_key_is_not_read() {
+        let cache = write(&word_list(&Options::default()));
+        assert!(read_word_cache(&cache[..], "fjord.txt").unwrap().is_none());
+        assert!(read_word_cache(&cache[..], "").unwrap().is_none());
+    }
+}
@@ -0,0 +1,340 @@
+//! Writing down how far a search got, so that it can pick up from there if it
+//! is interrupted.
+//!
+//! A checkpoint file starts with a line that says which search it is for, with
+//! a key that tells apart everything that changes the combinations. After that
+//! it has the combinations that were found from a starting word on a line
+//! each, with their words separated by spaces, followed by a line with `done`
+//! and the starting word. Anything after the last `done` line is from a word
+//! that wasn't finished, and is ignored, and cut off when the search resumes.
+
+use crate::find_word;
+use five_words::{ProgressSink, Word};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// What the first line of a checkpoint file starts with, before the key.
+const HEADER: &str = "five-words checkpoint ";
+
+/// A `ProgressSink` that passes every update on, and that appends the words
+/// that the search is done with to the checkpoint file, along with their
+/// combinations, once there are `every` of them.
+pub struct Checkpoint<'a> {
+    word_list: &'a [Word],
+    progress: &'a dyn ProgressSink,
+    every: usize,
+    state: Mutex<State>,
+}
+
+/// What the threads of the search share: the file, the lines for the words
+/// that haven't been written to it yet, and the first error that writing ran
+/// into, after which nothing more is written.
+struct State {
+    file: File,
+    pending: String,
+    pending_starts: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<'a> Checkpoint<'a> {
+    /// Opens the checkpoint file, keeping its first `keep` bytes, which is
+    /// what `read_checkpoint` read from it when resuming and 0 otherwise. A
+    /// file that is started over gets the line with the key first.
+    pub fn new(
+        path: &Path,
+        keep: u64,
+        key: &str,
+        every: usize,
+        word_list: &'a [Word],
+        progress: &'a dyn ProgressSink,
+    ) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.set_len(keep)?;
+        if keep == 0 {
+            writeln!(file, "{}{}", HEADER, key)?;
+            file.sync_data()?;
+        }
+        Ok(Checkpoint {
+            word_list,
+            progress,
+            every,
+            state: Mutex::new(State {
+                file,
+                pending: String::new(),
+                pending_starts: 0,
+                error: None,
+            }),
+        })
+    }
+
+    /// Writes the words that haven't been written yet, and returns the first
+    /// error that writing the file ran into, if any.
+    pub fn finish(self) -> std::io::Result<()> {
+        let mut state = self.state.into_inner().unwrap();
+        state.write();
+        match state.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl State {
+    fn write(&mut self) {
+        if self.error.is_none() {
+            let result = self.file.write_all(self.pending.as_bytes());
+            self.error = result.and_then(|_| self.file.sync_data()).err();
+        }
+        self.pending.clear();
+        self.pending_starts = 0;
+    }
+}
+
+impl ProgressSink for Checkpoint<'_> {
+    forward_progress!(
+        progress;
+        set_total,
+        tasks_are_pairs,
+        advance,
+        set_found,
+        set_current,
+        finish_task,
+        is_cancelled,
+    );
+
+    fn finish_start(&self, start: u32, combinations: &[Vec<u32>]) {
+        self.progress.finish_start(start, combinations);
+        let name = |i: u32| self.word_list[i as usize].original_word();
+        let mut lines = String::new();
+        for combination in combinations {
+            let names: Vec<&str> = combination.iter().map(|&i| name(i)).collect();
+            lines.push_str(&names.join(" "));
+            lines.push('\n');
+        }
+        lines.push_str("done ");
+        lines.push_str(name(start));
+        lines.push('\n');
+
+        let mut state = self.state.lock().unwrap();
+        state.pending.push_str(&lines);
+        state.pending_starts += 1;
+        if state.pending_starts >= self.every {
+            state.write();
+        }
+    }
+}
+
+/// The words that a checkpoint file says the search was done with.
+pub struct Resumed {
+    /// The indices of the words
+    pub done: HashSet<u32>,
+
+    /// The combinations that the search found from them
+    pub sequences: Vec<Vec<u32>>,
+
+    /// The number of bytes up to the end of the last `done` line, or 0 if
+    /// the file is empty
+    pub len: u64,
+}
+
+/// Reads a checkpoint file, which has to have been written with the same key,
+/// and, unless the combinations can have any number of words, combinations of
+/// `sequence_length` words.
+pub fn read_checkpoint(
+    path: &Path,
+    key: &str,
+    sequence_length: Option<usize>,
+    word_list: &[Word],
+) -> std::io::Result<Resumed> {
+    let input = std::fs::read_to_string(path)?;
+    let mut resumed = Resumed {
+        done: HashSet::new(),
+        sequences: vec![],
+        len: 0,
+    };
+    let invalid = |number: usize, message: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("line {} of {}: {}", number + 1, path.display(), message),
+        )
+    };
+    // A file that was only just created doesn't have anything to resume.
+    if input.is_empty() {
+        return Ok(resumed);
+    }
+    let mut lines = input.split_inclusive('\n').enumerate();
+    let header = lines.next().map_or("", |(_, line)| line);
+    if header.strip_prefix(HEADER).map(str::trim_end) != Some(key) {
+        return Err(invalid(
+            0,
+            String::from(
+                "the checkpoint is from another search, with other word lists or options, \
+                 so it can't be resumed",
+            ),
+        ));
+    }
+    // The lines since the last `done` line, which are only read once it's
+    // clear that they were written completely.
+    let mut pending = vec![];
+    let mut offset = header.len();
+    resumed.len = offset as u64;
+    for (number, line) in lines {
+        offset += line.len();
+        let start = match line.strip_prefix("done ") {
+            Some(start) if line.ends_with('\n') => start,
+            _ => {
+                pending.push((number, line));
+                continue;
+            }
+        };
+        let find = |number: usize, word: &str| {
+            find_word(word_list, word).map_err(|message| invalid(number, message))
+        };
+        resumed.done.insert(find(number, start.trim())?);
+        resumed.len = offset as u64;
+        for (number, line) in pending.drain(..) {
+            let sequence = line
+                .split_whitespace()
+                .map(|word| find(number, word))
+                .collect::<std::io::Result<Vec<u32>>>()?;
+            if let Some(len) = sequence_length.filter(|&len| len != sequence.len()) {
+                return Err(invalid(
+                    number,
+                    format!(
+                        "the combination has {} words instead of {}",
+                        sequence.len(),
+                        len
+                    ),
+                ));
+            }
+            resumed.sequences.push(sequence);
+        }
+    }
+    Ok(resumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use five_words::{get_words_from_lists, Constraints, NoProgress, Options, Search};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const KEY: &str = "words.txt 5";
+
+    /// Cancels the search once it's done with `after` starting words, as if
+    /// it was interrupted there.
+    struct StopAfter {
+        after: usize,
+        done: AtomicUsize,
+    }
+
+    impl ProgressSink for StopAfter {
+        fn set_total(&self, _n: u64) {}
+
+        fn advance(&self, _n: u64) {}
+
+        fn set_found(&self, _n: u64) {}
+
+        fn finish_start(&self, _start: u32, _combinations: &[Vec<u32>]) {
+            self.done.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.done.load(Ordering::Relaxed) >= self.after
+        }
+    }
+
+    /// Returns the words of a few shuffled alphabets cut into five-letter
+    /// pieces, so that many of them start a combination.
+    fn word_list() -> Vec<Word> {
+        let mut seed = 0x5eed_u64;
+        let mut list = String::new();
+        for _ in 0..12 {
+            let mut alphabet: Vec<u8> = (b'a'..=b'z').collect();
+            for i in (1..alphabet.len()).rev() {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                alphabet.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+            for word in alphabet.chunks(5).filter(|word| word.len() == 5) {
+                list.push_str(std::str::from_utf8(word).unwrap());
+                list.push('\n');
+            }
+        }
+        get_words_from_lists([list.as_bytes()], &Options::default()).unwrap()
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("five-words-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn resumed_search_finds_what_one_search_does() {
+        let word_list = word_list();
+        let path = path("resume");
+        let _ = std::fs::remove_file(&path);
+        let options = Options::default();
+        let expected =
+            Search::new(&word_list, 5, &options).find(&Constraints::default(), &NoProgress);
+
+        let stop = StopAfter {
+            after: 3,
+            done: AtomicUsize::new(0),
+        };
+        let checkpoint = Checkpoint::new(&path, 0, KEY, 1, &word_list, &stop).unwrap();
+        let interrupted =
+            Search::new(&word_list, 5, &options).find(&Constraints::default(), &checkpoint);
+        checkpoint.finish().unwrap();
+        assert!(interrupted.len() < expected.len());
+
+        let resumed = read_checkpoint(&path, KEY, Some(5), &word_list).unwrap();
+        assert!(!resumed.done.is_empty());
+        let options = Options {
+            skip_starts: resumed.done,
+            ..Options::default()
+        };
+        let checkpoint =
+            Checkpoint::new(&path, resumed.len, KEY, 1, &word_list, &NoProgress).unwrap();
+        let mut sequences = resumed.sequences;
+        sequences.extend(
+            Search::new(&word_list, 5, &options).find(&Constraints::default(), &checkpoint),
+        );
+        checkpoint.finish().unwrap();
+        sequences.sort_by_key(|sequence| sequence[0]);
+        assert_eq!(sequences, expected);
+
+        // The file now has every start, so resuming again finds nothing new.
+        let resumed = read_checkpoint(&path, KEY, Some(5), &word_list).unwrap();
+        let mut sequences = resumed.sequences;
+        sequences.sort_by_key(|sequence| sequence[0]);
+        assert_eq!(sequences, expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_of_another_search_is_refused() {
+        let word_list = word_list();
+        let path = path("other-search");
+        let _ = std::fs::remove_file(&path);
+        let checkpoint = Checkpoint::new(&path, 0, KEY, 1, &word_list, &NoProgress).unwrap();
+        Search::new(&word_list, 3, &Options::default()).find(&Constraints::default(), &checkpoint);
+        checkpoint.finish().unwrap();
+
+        let error = read_checkpoint(&path, "words.txt 3", Some(3), &word_list)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        // Even with the right key, combinations of another length are refused.
+        let error = read_checkpoint(&path, KEY, Some(5), &word_list)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(read_checkpoint(&path, KEY, Some(3), &word_list).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+//! Counting combinations without keeping them.
+
+use crate::search::Search;
+use crate::{Options, ProgressSink, Word};
+use rayon::prelude::*;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts the combinations of `sequence_length` disjoint words that leave each
+/// letter unused, with index 0 for the letter a. A combination that leaves
+/// several letters unused is counted for each of them, but five words of five
+/// letters leave exactly one. Also returns how many combinations there are,
+/// since the ones that leave no letter unused aren't in any of the counts.
+pub fn count_by_unused_letter(
+    word_list: &Vec<Word>,
+    sequence_length: usize,
+    options: &Options,
+    progress: &dyn ProgressSink,
+) -> ([u64; 26], u64) {
+    let search = Search::new(word_list, sequence_length, options);
+    let starts = search.starts();
+    let found = AtomicU64::new(0);
+    progress.set_total(starts.len() as u64);
+
+    let counts = starts.into_par_iter().map(|i| {
+        progress.set_current(&search.word(i).original_word);
+        let mut counts = [0; 26];
+        let mut len = 0;
+        let _ = search.visit_partial(
+            &mut vec![i],
+            0,
+            &search.first_candidates(i),
+            &mut |sequence| {
+                let mask = sequence
+                    .iter()
+                    .fold(0, |mask, &j| mask | search.word(j).mask);
+                for (letter, count) in counts.iter_mut().enumerate() {
+                    if mask & 1 << letter == 0 {
+                        *count += 1;
+                    }
+                }
+                len += 1;
+                ControlFlow::Continue(())
+            },
+        );
+        progress.set_found(found.fetch_add(len, Ordering::Relaxed) + len);
+        progress.advance(1);
+        (counts, len)
+    });
+
+    counts.reduce(
+        || ([0; 26], 0),
+        |(mut a, a_len), (b, b_len)| {
+            for (a, b) in a.iter_mut().zip(b) {
+                *a += b;
+            }
+            (a, a_len + b_len)
+        },
+    )
+}
+
+/// Counts the pairs of words in the list that don't have any letters in
+/// common. This is a lot cheaper than a search, and the more pairs there are,
+/// the longer a search takes.
+pub fn count_disjoint_pairs(word_list: &[Word]) -> u64 {
+    (0..word_list.len())
+        .into_par_iter()
+        .map(|i| {
+            word_list[i + 1..]
+                .iter()
+                .filter(|other| word_list[i].is_disjoint_with(other))
+                .count() as u64
+        })
+        .sum()
+}
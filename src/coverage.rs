@@ -0,0 +1,119 @@
+//! Ranking sets of disjoint words by the number of letters they cover.
+
+use crate::search::Search;
+use crate::{Options, ProgressSink, Word};
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A set of pairwise disjoint words, ordered by the number of distinct letters
+/// it covers. Ties are broken by the indices themselves, so that the entries
+/// that are kept do not depend on the order in which the threads find them.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Coverage {
+    letters: Reverse<u32>,
+    indices: Vec<u32>,
+}
+
+/// Returns the `k` sets of at most `sequence_length` pairwise disjoint words
+/// that cover the most distinct letters, best first.
+pub fn get_top_k_by_coverage(
+    word_list: &Vec<Word>,
+    sequence_length: usize,
+    k: usize,
+    options: &Options,
+    progress: &dyn ProgressSink,
+) -> Vec<Vec<u32>> {
+    let search = Search::new(word_list, sequence_length, options);
+    let starts = search.starts();
+    progress.set_total(starts.len() as u64);
+
+    let heaps = starts.into_par_iter().map(|i| {
+        progress.set_current(&search.word(i).original_word);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        get_top_k_by_coverage_partial(
+            &search,
+            k,
+            &mut heap,
+            &mut vec![i],
+            search.word(i).mask,
+            &search.first_candidates(i),
+        );
+        progress.advance(1);
+        heap
+    });
+
+    let mut heap = heaps.reduce(BinaryHeap::new, |mut a, b| {
+        for entry in b {
+            push_bounded(&mut a, entry, k);
+        }
+        a
+    });
+
+    let mut result = Vec::with_capacity(heap.len());
+    while let Some(entry) = heap.pop() {
+        result.push(entry.indices);
+    }
+    result.reverse();
+    result
+}
+
+/// Pushes the entry onto the heap, and then drops the worst entry if the heap
+/// holds more than `k` entries. The heap is a max-heap on `Coverage`, which
+/// means that its top is the set with the lowest coverage.
+fn push_bounded(heap: &mut BinaryHeap<Coverage>, entry: Coverage, k: usize) {
+    heap.push(entry);
+    if heap.len() > k {
+        heap.pop();
+    }
+}
+
+#[allow(clippy::ptr_arg)]
+fn get_top_k_by_coverage_partial(
+    search: &Search,
+    k: usize,
+    heap: &mut BinaryHeap<Coverage>,
+    state: &mut Vec<u32>,
+    mask: u32,
+    valid_indices: &Vec<u32>,
+) {
+    // Every state is pairwise disjoint, so every state is a candidate, not just
+    // the ones of the desired length.
+    let letters = mask.count_ones();
+    push_bounded(
+        heap,
+        Coverage {
+            letters: Reverse(letters),
+            indices: state.clone(),
+        },
+        k,
+    );
+
+    // Each word that is added covers at most as many letters as the longest
+    // word, and none of the excluded ones, so if the heap is full and even
+    // that can't beat its worst entry, this branch is done.
+    let remaining = (search.sequence_length - state.len()) as u32;
+    let best_possible = (letters + search.max_word_length * remaining).min(search.alphabet());
+    if remaining == 0 || heap.len() == k && heap.peek().unwrap().letters.0 >= best_possible {
+        return;
+    }
+
+    let last_index = *state.last().expect("state must not be empty");
+    let new_valid_indices = search.prune(last_index, valid_indices);
+
+    for next_index in new_valid_indices.iter() {
+        if !search.may_follow(state, 0, *next_index) {
+            continue;
+        }
+        state.push(*next_index);
+        get_top_k_by_coverage_partial(
+            search,
+            k,
+            heap,
+            state,
+            mask | search.word(*next_index).mask,
+            &new_valid_indices,
+        );
+        state.pop();
+    }
+}
@@ -0,0 +1,102 @@
+//! Going through the combinations a batch at a time, for frontends that only
+//! show the ones that are in view.
+
+use crate::search::{Search, Traversal};
+use std::ops::ControlFlow;
+
+/// The combinations of a search, computed a batch at a time as they are asked
+/// for, rather than all at once. Between batches, the cursor keeps where the
+/// traversal was, so the next batch goes on from there.
+///
+/// A cursor only keeps the candidates for each word of the combination it is
+/// at, which is at most a list of word indices per word, rather than every
+/// combination that was found, so its memory doesn't grow with the number of
+/// combinations. In exchange, it searches on a single thread, and how long a
+/// batch takes depends on how far apart the combinations are: a batch can
+/// take a long time if the combinations are rare, and the last call may have
+/// to search the rest of the words without finding anything.
+///
+/// The combinations come in the same order as from `Search::find` without
+/// constraints and without the pairs strategy.
+///
+/// ```
+/// use five_words::{get_words, Options, Search};
+///
+/// let input = "fjord\ngucks\nnymph\nvibex\nwaltz\nhello\njowls\n";
+/// let options = Options::default();
+/// let word_list = get_words(input.as_bytes(), &options).unwrap();
+/// let search = Search::new(&word_list, 5, &options);
+/// let mut cursor = search.cursor();
+/// assert_eq!(cursor.next_batch(100).len(), 1);
+/// assert!(cursor.next_batch(100).is_empty());
+/// assert!(cursor.is_done());
+/// ```
+pub struct SolutionCursor<'s, 'a> {
+    search: &'s Search<'a>,
+    starts: Vec<u32>,
+
+    /// The position in `starts` of the next word to start from
+    next_start: usize,
+
+    /// The words of the combination that the traversal is at
+    state: Vec<u32>,
+
+    /// The traversal from the last start, if it isn't done
+    traversal: Option<Traversal>,
+}
+
+impl<'s, 'a> SolutionCursor<'s, 'a> {
+    pub(crate) fn new(search: &'s Search<'a>) -> Self {
+        SolutionCursor {
+            search,
+            starts: search.starts(),
+            next_start: 0,
+            state: vec![],
+            traversal: None,
+        }
+    }
+
+    /// Returns the next `n` combinations, or fewer if there aren't that many
+    /// left, which is none once the search is done.
+    pub fn next_batch(&mut self, n: usize) -> Vec<Vec<u32>> {
+        let one_per_start = self.search.options.one_per_start;
+        let mut batch = vec![];
+        while batch.len() < n {
+            if let Some(traversal) = &mut self.traversal {
+                let flow = self
+                    .search
+                    .resume(traversal, &mut self.state, &mut |sequence| {
+                        batch.push(sequence.to_vec());
+                        match one_per_start || batch.len() == n {
+                            true => ControlFlow::Break(()),
+                            false => ControlFlow::Continue(()),
+                        }
+                    });
+                if flow.is_continue() || one_per_start {
+                    self.traversal = None;
+                }
+                continue;
+            }
+
+            let i = match self.starts.get(self.next_start) {
+                Some(&i) => i,
+                None => break,
+            };
+            self.next_start += 1;
+            self.state = vec![i];
+            let (complete, traversal) =
+                self.search
+                    .begin(&self.state, 0, &self.search.first_candidates(i));
+            if complete {
+                batch.push(self.state.clone());
+            }
+            self.traversal = traversal;
+        }
+        batch
+    }
+
+    /// Returns `true` if every combination has been returned.
+    pub fn is_done(&self) -> bool {
+        self.traversal.is_none() && self.next_start == self.starts.len()
+    }
+}
@@ -0,0 +1,102 @@
+//! Writing the diagnostics of the library to standard error, with the
+//! messages that matter most styled so that they stand out.
+
+use console::{style, Style};
+use std::fmt::{self, Write};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Formats each event like tracing's default format does without the time
+/// and the target, as its level, the spans it's in, its message and its other
+/// fields. The combinations that are found stand out in bold green, the
+/// anagrams that are left out in yellow, and the words that are added are
+/// dimmed. The styles go by whether `console` colors standard error.
+pub struct Diagnostics;
+
+/// Returns the style for an event with the message.
+fn style_of(message: &str) -> Style {
+    let style = Style::new().for_stderr();
+    match message {
+        "found a combination" => style.green().bold(),
+        "skipping an anagram of a word that is already in the list" => style.yellow(),
+        "adding a word to the list" => style.dim(),
+        _ => style,
+    }
+}
+
+/// The fields of an event, with the message apart from the others.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    others: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(
+                self.others,
+                " {}{}{:?}",
+                style(field.name()).for_stderr().italic(),
+                style("=").for_stderr().dim(),
+                value
+            );
+        }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for Diagnostics
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let level = *event.metadata().level();
+        let level_style = Style::new().for_stderr();
+        let level_style = match level {
+            Level::ERROR => level_style.red(),
+            Level::WARN => level_style.yellow(),
+            Level::INFO => level_style.green(),
+            Level::DEBUG => level_style.blue(),
+            Level::TRACE => level_style.magenta(),
+        };
+        write!(writer, "{} ", level_style.apply_to(format!("{:>5}", level)))?;
+
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                write!(writer, "{}", style(span.name()).for_stderr().bold())?;
+                if let Some(fields) = span.extensions().get::<FormattedFields<N>>() {
+                    if !fields.is_empty() {
+                        write!(
+                            writer,
+                            "{}{}{}",
+                            style("{").for_stderr().bold(),
+                            fields,
+                            style("}").for_stderr().bold()
+                        )?;
+                    }
+                }
+                write!(writer, "{} ", style(":").for_stderr().dim())?;
+            }
+        }
+
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        writeln!(
+            writer,
+            "{}{}",
+            style_of(&fields.message).apply_to(&fields.message),
+            fields.others
+        )
+    }
+}
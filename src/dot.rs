@@ -0,0 +1,46 @@
+//! Writing the combinations as a GraphViz graph of which words they contain.
+
+use crate::output::Columns;
+use five_words::Word;
+use std::io::Write;
+
+/// Writes an undirected graph with a node for every combination and one for
+/// every word in any of them, and an edge from each combination to each of
+/// its words. So the words that are in many combinations have many edges.
+pub fn write_matches<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    sequences: &[Vec<u32>],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    writeln!(out, "graph matches {{")?;
+    let mut used = vec![false; word_list.len()];
+    for &i in sequences.iter().flatten() {
+        used[i as usize] = true;
+    }
+    for (i, word) in word_list.iter().enumerate().filter(|&(i, _)| used[i]) {
+        writeln!(
+            out,
+            "  w{} [label=\"{}\", shape=ellipse];",
+            i,
+            escape(&columns.word(word))
+        )?;
+    }
+    for (number, sequence) in sequences.iter().enumerate() {
+        writeln!(
+            out,
+            "  c{} [label=\"#{}\", shape=box];",
+            number + 1,
+            number + 1
+        )?;
+        for &i in sequence {
+            writeln!(out, "  c{} -- w{};", number + 1, i)?;
+        }
+    }
+    writeln!(out, "}}")
+}
+
+/// Escapes the characters that have a special meaning in a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,82 @@
+//! Scoring combinations by how rare their words are, and ordering letters by
+//! how rare they are.
+
+use crate::Word;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Reads a list of word frequencies, with a word and the number of times it
+/// occurs on each line, separated by whitespace. The counts of words that
+/// appear more than once are added up.
+pub fn read_frequencies<T: Read>(mut reader: T) -> std::io::Result<HashMap<String, u64>> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut frequencies = HashMap::new();
+    for (number, line) in input.lines().enumerate() {
+        let mut fields = line.split_whitespace();
+        let (word, count) = match (fields.next(), fields.next(), fields.next()) {
+            (None, _, _) => continue,
+            (Some(word), Some(count), None) => (word, count.parse::<u64>().ok()),
+            (Some(word), _, _) => (word, None),
+        };
+        let count = count.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "line {} of the frequency list should be a word and a count, not \"{}\"",
+                    number + 1,
+                    line
+                ),
+            )
+        })?;
+        *frequencies.entry(String::from(word)).or_insert(0) += count;
+    }
+    Ok(frequencies)
+}
+
+/// Returns the difficulty of each word in the list, which is `1 / (n + 1)` for
+/// a word that occurs `n` times according to the frequencies. The rarer the
+/// word, the closer its difficulty is to 1, which is the difficulty of words
+/// that don't occur at all.
+pub fn get_difficulties(word_list: &[Word], frequencies: &HashMap<String, u64>) -> Vec<f64> {
+    word_list
+        .iter()
+        .map(|word| {
+            let count = frequencies.get(word.original_word()).copied().unwrap_or(0);
+            1.0 / (count as f64 + 1.0)
+        })
+        .collect()
+}
+
+/// Returns the difficulty of a combination, which is the sum of the
+/// difficulties of its words.
+pub fn get_difficulty(sequence: &[u32], difficulties: &[f64]) -> f64 {
+    sequence.iter().map(|&i| difficulties[i as usize]).sum()
+}
+
+/// How often each letter from `a` to `z` occurs in English text, as a fraction
+/// of all letters, according to Robert Lewand's counts.
+pub const ENGLISH_LETTER_FREQUENCIES: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094, 0.06966, 0.00153,
+    0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056,
+    0.02758, 0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
+
+/// Returns the letters from `a` to `z`, from the rarest to the most common
+/// according to `frequencies`, which has an entry for each letter in
+/// alphabetical order. Letters that are as common as each other stay in
+/// alphabetical order.
+///
+/// ```
+/// use five_words::{letters_by_frequency, ENGLISH_LETTER_FREQUENCIES};
+/// let letters = letters_by_frequency(&ENGLISH_LETTER_FREQUENCIES);
+/// assert_eq!(&letters[..4], b"zqxj");
+/// ```
+pub fn letters_by_frequency(frequencies: &[f64; 26]) -> [u8; 26] {
+    let mut letters: [u8; 26] = std::array::from_fn(|i| b'a' + i as u8);
+    letters.sort_by(|a, b| {
+        frequencies[(a - b'a') as usize].total_cmp(&frequencies[(b - b'a') as usize])
+    });
+    letters
+}
@@ -0,0 +1,117 @@
+//! Finding a single combination quickly, by covering the rarest letters first.
+
+use crate::{letters_by_frequency, Options, Word, ENGLISH_LETTER_FREQUENCIES};
+
+/// Returns a combination of `sequence_length` disjoint words, or `None` if
+/// there isn't any, with its indices in increasing order. Rather than going
+/// through the words in order, the search picks the letter that the fewest of
+/// the remaining words have, and first tries each of those words, and only
+/// then leaving that letter out. Letters that are tied go from the rarest in
+/// English. This finds a combination in a fraction of the time that the full
+/// search takes to get to its first one, but which one it finds is not the
+/// first one in the order of the full search.
+///
+/// The words have to be in the allowed words, if there are any. The slots,
+/// the banned pairs, the letter budget, the shard, the starts to skip or to
+/// stop before, the same pattern and the other options that only the full
+/// search knows about are ignored.
+pub fn find_greedy(
+    word_list: &[Word],
+    sequence_length: usize,
+    options: &Options,
+) -> Option<Vec<u32>> {
+    let candidates: Vec<u32> = (0..word_list.len() as u32)
+        .filter(|&i| {
+            options
+                .allowed_words
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(word_list[i as usize].original_word()))
+        })
+        .collect();
+    let greedy = Greedy {
+        word_list,
+        sequence_length,
+        letters: letters_by_frequency(&ENGLISH_LETTER_FREQUENCIES),
+    };
+    let mut state = vec![];
+    if !greedy.find(&mut state, &candidates) {
+        return None;
+    }
+    state.sort_unstable();
+    Some(state)
+}
+
+struct Greedy<'a> {
+    word_list: &'a [Word],
+    sequence_length: usize,
+
+    /// The letters from the rarest in English to the most common, which is
+    /// the order that ties are broken in
+    letters: [u8; 26],
+}
+
+impl Greedy<'_> {
+    fn mask(&self, i: u32) -> u32 {
+        self.word_list[i as usize].mask
+    }
+
+    /// Adds words from the candidates, which are all disjoint with the state,
+    /// until the state has enough of them, and returns `true` if it does.
+    fn find(&self, state: &mut Vec<u32>, candidates: &[u32]) -> bool {
+        let remaining = self.sequence_length - state.len();
+        if remaining == 0 {
+            return true;
+        }
+
+        let mut counts = [0usize; 26];
+        let mut union = 0;
+        let mut shortest = u32::MAX;
+        for &i in candidates {
+            let mask = self.mask(i);
+            union |= mask;
+            shortest = shortest.min(mask.count_ones());
+            for (letter, count) in counts.iter_mut().enumerate() {
+                *count += (mask >> letter & 1) as usize;
+            }
+        }
+        // Even the shortest words need more letters than are left between
+        // them.
+        if candidates.len() < remaining
+            || (union.count_ones() as usize) < remaining * shortest as usize
+        {
+            return false;
+        }
+
+        let letter = self
+            .letters
+            .iter()
+            .map(|&c| (c - b'a') as usize)
+            .filter(|&letter| counts[letter] > 0)
+            .min_by_key(|&letter| counts[letter])
+            .expect("the candidates have letters");
+        let bit = 1 << letter;
+
+        for &i in candidates.iter().filter(|&&i| self.mask(i) & bit != 0) {
+            let mask = self.mask(i);
+            let next: Vec<u32> = candidates
+                .iter()
+                .copied()
+                .filter(|&j| self.mask(j) & mask == 0)
+                .collect();
+            state.push(i);
+            if self.find(state, &next) {
+                return true;
+            }
+            state.pop();
+        }
+
+        // Every combination either has a word with the letter, which were
+        // all tried, or none of its words have it.
+        let without: Vec<u32> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| self.mask(i) & bit == 0)
+            .collect();
+        self.find(state, &without)
+    }
+}
@@ -0,0 +1,145 @@
+//! Writing the combinations as a self-contained HTML page.
+
+use crate::output::Columns;
+use five_words::{get_difficulty, letters_of, Word};
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Everything on the page up to the rows of the table, apart from the header
+/// cells, which depend on the columns.
+const HEADER: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Five words</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; text-align: left; }
+th { border-bottom: 1px solid #888; }
+tbody tr:nth-child(even) { background: #f0f0f0; }
+td { font-family: monospace; }
+</style>
+</head>
+<body>
+<h1>Five words</h1>
+<p><input id="filter" type="search" placeholder="Filter" autofocus> <span id="count"></span></p>
+<table>
+<thead>
+<tr>"#;
+
+/// Everything on the page after the rows of the table, including the script
+/// that hides the rows that don't match the filter.
+const FOOTER: &str = r#"</tbody>
+</table>
+<script>
+const filter = document.getElementById("filter");
+const count = document.getElementById("count");
+const rows = Array.from(document.querySelectorAll("tbody tr"));
+function update() {
+  const text = filter.value.trim().toLowerCase();
+  let shown = 0;
+  for (const row of rows) {
+    const match = row.textContent.includes(text);
+    row.hidden = !match;
+    shown += match;
+  }
+  count.textContent = shown + " of " + rows.length + " combinations";
+}
+filter.addEventListener("input", update);
+update();
+</script>
+</body>
+</html>
+"#;
+
+/// Writes a page with a table of the combinations, with a column per word and
+/// a column with the letters that each combination leaves unused, along with
+/// the extra columns.
+pub fn write_page<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    sequences: &[Vec<u32>],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    let width = sequences.iter().map(Vec::len).max().unwrap_or(0);
+
+    write!(out, "{}", HEADER)?;
+    if columns.number {
+        write!(out, "<th>#</th>")?;
+    }
+    if columns.word_count {
+        write!(out, "<th>Words</th>")?;
+    }
+    for i in 0..width {
+        write!(out, "<th>Word {}</th>", i + 1)?;
+    }
+    write!(out, "<th>Unused</th>")?;
+    if columns.signature {
+        write!(out, "<th>Letters</th>")?;
+    }
+    if columns.letter_map {
+        write!(out, "<th>Letter map</th>")?;
+    }
+    if columns.positions {
+        write!(out, "<th>Positions</th>")?;
+    }
+    if columns.difficulty.is_some() {
+        write!(out, "<th>Difficulty</th>")?;
+    }
+    writeln!(out, "</tr>\n</thead>\n<tbody>")?;
+
+    for (number, sequence) in sequences.iter().enumerate() {
+        write!(out, "<tr>")?;
+        if columns.number {
+            write!(out, "<td>{}</td>", number + 1)?;
+        }
+        if columns.word_count {
+            write!(out, "<td>{}</td>", sequence.len())?;
+        }
+        for i in 0..width {
+            let word = sequence
+                .get(i)
+                .map_or(Cow::Borrowed(""), |&i| columns.word(&word_list[i as usize]));
+            write!(out, "<td>{}</td>", escape(&word))?;
+        }
+        let mask = sequence
+            .iter()
+            .fold(0, |mask, i| mask | word_list[*i as usize].mask());
+        write!(out, "<td>{}</td>", letters_of(!mask))?;
+        if columns.signature {
+            write!(out, "<td>{}</td>", letters_of(mask))?;
+        }
+        if columns.letter_map {
+            write!(
+                out,
+                "<td><code>{}</code></td>",
+                Columns::letter_map(sequence, word_list)
+            )?;
+        }
+        if columns.positions {
+            write!(
+                out,
+                "<td><code>{}</code></td>",
+                Columns::positions(sequence, word_list)
+            )?;
+        }
+        if let Some(difficulties) = &columns.difficulty {
+            write!(
+                out,
+                "<td>{:.4}</td>",
+                get_difficulty(sequence, difficulties)
+            )?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    write!(out, "{}", FOOTER)
+}
+
+/// Escapes the characters that have a special meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,235 @@
+//! Reading word lists.
+
+use crate::{GroupKey, Options, Word};
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+use std::io::Read;
+use tracing::{debug, enabled, info, warn, Level};
+
+/// The target of the events with every line of the input exactly as it was
+/// read, which are only there if a subscriber asks for this target, since
+/// they're only useful to find out why words go missing.
+pub const ECHO_TARGET: &str = "echo_input";
+
+/// Transcodes the input to UTF-8 from the given encoding, or checks that it
+/// already is valid UTF-8 if no encoding is given.
+fn decode(bytes: Vec<u8>, encoding: Option<&'static Encoding>) -> std::io::Result<String> {
+    match encoding {
+        Some(encoding) => Ok(encoding.decode(&bytes).0.into_owned()),
+        None => String::from_utf8(bytes).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "the input is not valid UTF-8 (invalid byte at offset {})",
+                    e.utf8_error().valid_up_to()
+                ),
+            )
+        }),
+    }
+}
+
+/// The words that have been read so far, in the order in which they were
+/// first read. Keeping that order, rather than whatever order a hash set
+/// happens to iterate in, makes the word list and therefore the output the
+/// same on every run.
+#[derive(Default)]
+struct WordSet {
+    words: Vec<Word>,
+
+    /// The position in `words` of the word with each key
+    positions: HashMap<Box<[u8]>, usize>,
+}
+
+/// Reads a list of words, one per line, and returns the ones that consist of
+/// different lowercase ASCII letters, and that have five letters unless the
+/// options allow other lengths, in the order they appear in. Of
+/// each set of anagrams, only the first one is kept, unless `keep_anagrams` is
+/// set.
+pub fn get_words<T: Read>(input_reader: T, options: &Options) -> std::io::Result<Vec<Word>> {
+    get_words_from_lists([input_reader], options)
+}
+
+/// Does the same as `get_words`, but for several lists at once, which are
+/// merged into a single word list. Each `Word` remembers which of the lists it
+/// appeared in, so at most 32 lists can be read.
+pub fn get_words_from_lists<T: Read>(
+    input_readers: impl IntoIterator<Item = T>,
+    options: &Options,
+) -> std::io::Result<Vec<Word>> {
+    get_words_from_encoded_lists(
+        input_readers
+            .into_iter()
+            .map(|input_reader| (input_reader, options.encoding)),
+        options,
+    )
+}
+
+/// Does the same as `get_words_from_lists`, but with an encoding for each
+/// list, which takes the place of the one in the options, so that lists in
+/// different encodings can be merged. A list without an encoding has to be
+/// valid UTF-8.
+pub fn get_words_from_encoded_lists<T: Read>(
+    input_readers: impl IntoIterator<Item = (T, Option<&'static Encoding>)>,
+    options: &Options,
+) -> std::io::Result<Vec<Word>> {
+    let mut word_set = WordSet::default();
+    for (list, (input_reader, encoding)) in input_readers.into_iter().enumerate() {
+        assert!(list < 32, "at most 32 word lists can be read");
+        read_list(input_reader, 1 << list, encoding, &mut word_set, options)?;
+    }
+    let mut words = word_set.words;
+    if options.excluded_letters != 0 {
+        for word in &mut words {
+            word.renumber(options.excluded_letters);
+        }
+    }
+    Ok(words)
+}
+
+/// Reports every line of the input before it is decoded, with its length and
+/// its bytes in hexadecimal, so that carriage returns, byte order marks and
+/// other bytes that keep a line from being a word show up.
+fn echo(bytes: &[u8]) {
+    for (number, line) in bytes.split_inclusive(|&c| c == b'\n').enumerate() {
+        let hex: Vec<String> = line.iter().map(|c| format!("{:02x}", c)).collect();
+        info!(
+            target: ECHO_TARGET,
+            line = number + 1,
+            len = line.len(),
+            text = ?String::from_utf8_lossy(line),
+            bytes = %hex.join(" "),
+            "read a line"
+        );
+    }
+}
+
+/// Reads a single list of words in the given encoding into the set, marking
+/// the words in it as coming from `source`.
+fn read_list<T: Read>(
+    mut input_reader: T,
+    source: u32,
+    encoding: Option<&'static Encoding>,
+    word_set: &mut WordSet,
+    options: &Options,
+) -> std::io::Result<()> {
+    let mut bytes = Vec::new();
+
+    input_reader.read_to_end(&mut bytes)?;
+    // An empty list is easily mistaken for one without any usable words, but
+    // it usually means that the wrong file was given, or that nothing was
+    // piped in.
+    let list = source.trailing_zeros() + 1;
+    if bytes.is_empty() {
+        warn!(list, "the word list is empty");
+        return Ok(());
+    }
+    if enabled!(target: ECHO_TARGET, Level::INFO) {
+        echo(&bytes);
+    }
+    let input = decode(bytes, encoding)?;
+    let lines: Box<dyn Iterator<Item = &str>> = if options.tokenize {
+        // Punctuation around a word, as in running text, isn't part of it,
+        // and commas separate words even without a space, as in a JSON array.
+        Box::new(
+            input
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .map(|token| token.trim_matches(|c: char| c.is_ascii_punctuation()))
+                .filter(|token| !token.is_empty()),
+        )
+    } else {
+        Box::new(input.lines())
+    };
+
+    if options.assume_sorted_input {
+        let read_before = word_set.words.len();
+        // Checking the length is cheap, and it leaves out blank lines, which
+        // would otherwise be words without letters.
+        word_set.words.extend(
+            lines
+                .filter(|line| options.accepts_length(line.len()))
+                .map(|line| {
+                    let mut word = Word::from_valid(line);
+                    word.sources = source;
+                    word
+                })
+                .filter(|word| word.mask & options.excluded_letters == 0)
+                .filter(|word| options.vowels.is_none_or(|vowels| word.mask & vowels != 0)),
+        );
+        if word_set.words.len() == read_before {
+            warn!(list, "the word list has no words that can be used");
+        }
+        return Ok(());
+    }
+
+    let mut usable = 0;
+
+    for line in lines
+        .filter(|l| options.accepts_length(l.len()) && l.bytes().all(|c| c.is_ascii_lowercase()))
+    {
+        // Words with repeated letters can't be part of any combination.
+        let mut word: Word = match line.parse() {
+            Ok(word) => word,
+            Err(_) => continue,
+        };
+
+        if word.mask() & options.excluded_letters != 0 {
+            debug!(word = line, "leaving out a word with an excluded letter");
+            continue;
+        }
+        if options
+            .vowels
+            .is_some_and(|vowels| word.mask() & vowels == 0)
+        {
+            debug!(word = line, "leaving out a word without a vowel");
+            continue;
+        }
+        word.sources = source;
+        usable += 1;
+        match options.group_key {
+            _ if options.keep_anagrams => word.key = line.as_bytes().into(),
+            GroupKey::Anagram => {}
+            GroupKey::Skeleton => {
+                word.key = word
+                    .word
+                    .iter()
+                    .copied()
+                    .filter(|c| !b"aeiou".contains(c))
+                    .collect()
+            }
+            GroupKey::Exact => word.key = line.as_bytes().into(),
+        }
+
+        // This check is not strictly necessary to insert the Word, but it's
+        // here because of the diagnostics, to debug the anagram logic, and
+        // to keep track of the lists that the word appears in.
+        if let Some(&position) = word_set.positions.get(&word.key) {
+            let existing = &mut word_set.words[position];
+            // The word can be used wherever a word from either list can be.
+            existing.sources |= source;
+            // The same word appearing twice is most likely a quirk of the
+            // input, while anagrams are expected, so they're told apart.
+            if existing.original_word == word.original_word {
+                info!(word = %word.original_word, "skipping a duplicate word");
+            } else {
+                info!(
+                    word = %word.original_word,
+                    existing = %existing.original_word,
+                    "skipping an anagram of a word that is already in the list"
+                );
+                if !existing.anagrams.contains(&word.original_word) {
+                    existing.anagrams.push(word.original_word);
+                }
+            }
+        } else {
+            debug!(word = ?word, "adding a word to the list");
+            word_set
+                .positions
+                .insert(word.key.clone(), word_set.words.len());
+            word_set.words.push(word);
+        }
+    }
+    if usable == 0 {
+        warn!(list, "the word list has no words that can be used");
+    }
+    Ok(())
+}
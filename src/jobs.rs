@@ -0,0 +1,78 @@
+//! Recording how long each task of the search took, to see how evenly the work
+//! is spread over the threads.
+
+use five_words::{ProgressSink, Word};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A `ProgressSink` that passes every update on, and that keeps the time and
+/// the number of combinations of every task, along with the thread it ran on.
+pub struct JobsOutput<'a> {
+    progress: &'a dyn ProgressSink,
+    jobs: Mutex<Vec<Job>>,
+}
+
+struct Job {
+    start: u32,
+    thread: Option<usize>,
+    elapsed: Duration,
+    found: usize,
+}
+
+impl<'a> JobsOutput<'a> {
+    pub fn new(progress: &'a dyn ProgressSink) -> Self {
+        JobsOutput {
+            progress,
+            jobs: Mutex::new(vec![]),
+        }
+    }
+
+    /// Writes a line for every task to a new file at the given path, in the
+    /// order of the words they started from, with the index and the word, the
+    /// thread, the time in seconds and the number of combinations, separated
+    /// by tabs.
+    pub fn write(&self, path: &Path, word_list: &[Word]) -> std::io::Result<()> {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.sort_by_key(|job| job.start);
+        let mut out = BufWriter::new(File::create(path)?);
+        for job in jobs.iter() {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{:.6}\t{}",
+                job.start,
+                word_list[job.start as usize].original_word(),
+                job.thread
+                    .map_or(String::from("-"), |thread| thread.to_string()),
+                job.elapsed.as_secs_f64(),
+                job.found
+            )?;
+        }
+        out.flush()
+    }
+}
+
+impl ProgressSink for JobsOutput<'_> {
+    forward_progress!(
+        progress;
+        set_total,
+        tasks_are_pairs,
+        advance,
+        set_found,
+        set_current,
+        finish_start,
+        is_cancelled,
+    );
+
+    fn finish_task(&self, start: u32, elapsed: Duration, found: usize) {
+        self.progress.finish_task(start, elapsed, found);
+        self.jobs.lock().unwrap().push(Job {
+            start,
+            thread: rayon::current_thread_index(),
+            elapsed,
+            found,
+        });
+    }
+}
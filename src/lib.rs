@@ -0,0 +1,319 @@
+//! The search for combinations of words that don't have any characters in
+//! common. The binary in `main.rs` is a thin command line interface on top of
+//! this library.
+
+mod cache;
+mod count;
+mod coverage;
+mod cursor;
+mod frequency;
+mod greedy;
+mod input;
+mod search;
+mod solutions;
+mod word;
+
+pub use cache::{read_word_cache, write_word_cache};
+pub use count::{count_by_unused_letter, count_disjoint_pairs};
+pub use coverage::get_top_k_by_coverage;
+pub use cursor::SolutionCursor;
+pub use frequency::{
+    get_difficulties, get_difficulty, letters_by_frequency, read_frequencies,
+    ENGLISH_LETTER_FREQUENCIES,
+};
+pub use greedy::find_greedy;
+pub use input::{get_words, get_words_from_encoded_lists, get_words_from_lists, ECHO_TARGET};
+pub use search::{
+    get_disjoint_indices, remove_words_without_partners, solve_from_strings, Constraints, Search,
+    TreeSize,
+};
+pub use solutions::Solutions;
+pub use word::{all_characters_unique, letters_of, ParseWordError, Word};
+
+use encoding_rs::Encoding;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+/// Settings that control how the input is read and how the search is run. What
+/// the library reports along the way goes through `tracing`, which the caller
+/// decides what to do with.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// The encoding of the input, which is transcoded to UTF-8 before it is
+    /// parsed. If this is `None`, the input has to be valid UTF-8.
+    pub encoding: Option<&'static Encoding>,
+
+    /// What the parallel search starts from
+    pub strategy: Strategy,
+
+    /// Keep every spelling of a set of letters in the word list, instead of
+    /// only the first one. Since anagrams share all their letters they never
+    /// end up in the same combination, but every combination with one of them
+    /// is repeated for each of its anagrams.
+    pub keep_anagrams: bool,
+
+    /// What makes two words the same word, of which only the first one is
+    /// kept. Keeping anagrams is the same as `GroupKey::Exact`.
+    pub group_key: GroupKey,
+
+    /// For each position in a combination, the set of lists that the word in
+    /// that position has to come from, with bit 0 for the first list. If this
+    /// is `None`, any word can go anywhere.
+    pub slot_sources: Option<Vec<u32>>,
+
+    /// For each position in a combination, what else the word in that
+    /// position has to be like. If this is `None`, any word can go anywhere.
+    /// Like the slot sources, the positions that have the same constraints
+    /// can have their words in any order.
+    pub slot_constraints: Option<Vec<SlotConstraint>>,
+
+    /// The number of bytes that the search may use to precompute which words
+    /// are disjoint with which. If that wouldn't fit, the search checks as it
+    /// goes instead. If this is `None`, there is no limit.
+    pub max_memory: Option<u64>,
+
+    /// Only search the combinations that start with the words in this shard
+    /// of the word list. If this is `None`, all of them are searched.
+    pub shard: Option<Shard>,
+
+    /// The only words that the search may choose for a combination. Other
+    /// words are still read, so that their anagrams are left out like they
+    /// would be otherwise. If this is `None`, every word may be chosen.
+    pub allowed_words: Option<HashSet<String>>,
+
+    /// Look for sets of words of any size that cover exactly this many
+    /// distinct letters, instead of for sets of `sequence_length` words
+    pub letters_covered: Option<u32>,
+
+    /// Look for the maximal sets of disjoint words, of any size, which are
+    /// the sets that no other word that may be chosen can be added to,
+    /// instead of for sets of `sequence_length` words
+    pub maximal: bool,
+
+    /// Only find the first combination that starts with each word that the
+    /// search starts from, which is a sample of combinations with as many
+    /// different first words as possible
+    pub one_per_start: bool,
+
+    /// Only search the combinations whose first word is among this many words
+    /// at the start of the list. Without slots, the words in a combination are
+    /// in the same order as in the list, so these are exactly the combinations
+    /// with at least one of those words. If this is `None`, any word can start
+    /// a combination.
+    pub starts_before: Option<usize>,
+
+    /// The number of words in a combination up to which the search splits
+    /// into a parallel task for every next word. Beyond that, each task
+    /// searches on its own. The search always has a task per word it starts
+    /// from, so both 0 and 1 mean only those.
+    pub max_depth_parallel: usize,
+
+    /// The numbers of letters that the words that are read may have. If this
+    /// is `None`, only words of five letters are read.
+    pub word_lengths: Option<RangeInclusive<usize>>,
+
+    /// Pairs of words that may not be in the same combination, even though
+    /// they're disjoint, as indices into the word list with the lower index
+    /// first. Like the allowed words, these can only be set once the word list
+    /// has been read.
+    pub banned_pairs: HashSet<(u32, u32)>,
+
+    /// The indices of the words that the search doesn't start from, such as
+    /// the ones that an earlier run that was interrupted had already finished.
+    pub skip_starts: HashSet<u32>,
+
+    /// The number of letters that the words in a combination may share in
+    /// total, where a letter that three words have counts twice. If this is
+    /// `None`, the words have to be disjoint. The search with a budget doesn't
+    /// start from pairs, and the top sets by coverage ignore it.
+    pub letter_budget: Option<u32>,
+
+    /// Trust that every line of the input is a word of different lowercase
+    /// letters, and that no two lines are anagrams of each other, not even
+    /// across lists, and use every line of an accepted length as it is,
+    /// without checking any of that. This saves a little time on word lists
+    /// that have been filtered already, but the results are wrong if they
+    /// haven't been.
+    pub assume_sorted_input: bool,
+
+    /// Read every word on every line of the input, separated by whitespace,
+    /// instead of every line as a single word, so that the words can be taken
+    /// from any text. Punctuation at the start and the end of each word is
+    /// left out.
+    pub tokenize: bool,
+
+    /// The letters that the words that are read may not have, with bit 0 for
+    /// the letter a. The search numbers the letters that are left densely from
+    /// bit 0, but `Word::mask` still has a bit per letter of the whole
+    /// alphabet, so that the letters that combinations leave unused include
+    /// these.
+    pub excluded_letters: u32,
+
+    /// The letters that count as vowels, with bit 0 for the letter a, of
+    /// which the words that are read need at least one. If this is `None`,
+    /// words without vowels are read as well.
+    pub vowels: Option<u32>,
+
+    /// Only find combinations of words that all have the same pattern of
+    /// vowels and consonants, such as "fjord" and "nymph", which are both
+    /// consonant, consonant, vowel, consonant, consonant. Only "aeiou" are
+    /// vowels.
+    pub same_pattern: bool,
+
+    /// Only find combinations whose words all start with different letters.
+    /// Disjoint words always do, so this only matters with a letter budget.
+    pub distinct_first_letters: bool,
+
+    /// Check, at every step of the search, that the words that may still be
+    /// added to a combination are the ones that the pruning should have left,
+    /// and panic if they aren't. This makes the search slower, and is only
+    /// meant to catch mistakes in the search itself.
+    pub debug_invariants: bool,
+}
+
+impl Options {
+    /// Returns `true` if words of `len` letters are read.
+    pub fn accepts_length(&self, len: usize) -> bool {
+        match &self.word_lengths {
+            Some(lengths) => lengths.contains(&len),
+            None => len == 5,
+        }
+    }
+}
+
+/// One of `count` parts of the search, which together cover every combination
+/// exactly once. The shard with index `index` has the combinations whose first
+/// word is at a position `i` in the word list where `i % count == index`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// Returns `true` if the combinations that start with the word at `i` are
+    /// in this shard.
+    pub fn contains(&self, i: usize) -> bool {
+        i % self.count == self.index
+    }
+}
+
+/// The ways in which `get_disjoint_indices` can divide the search into tasks.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// One task per word, which searches all combinations that start with it
+    #[default]
+    Words,
+
+    /// First find all pairs of disjoint words, and then use one task per pair.
+    /// This makes for more, smaller tasks, and the pairs double as the list of
+    /// candidates for each first word.
+    Pairs,
+}
+
+/// The letters that the word in a slot has to start or end with, have some
+/// of, or not have, each with bit 0 for the letter a. The constraints that are
+/// `None` allow any word.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotConstraint {
+    /// The letters that the word may start with
+    pub first_letters: Option<u32>,
+
+    /// The letters that the word may end with
+    pub last_letters: Option<u32>,
+
+    /// The letters of which the word needs at least one
+    pub some_of: Option<u32>,
+
+    /// The letters that the word may not have
+    pub none_of: u32,
+}
+
+impl SlotConstraint {
+    /// Returns `true` if the word meets every constraint.
+    pub fn allows(&self, word: &Word) -> bool {
+        let bit = |c: u8| 1 << (c.wrapping_sub(b'a') & 31);
+        let spelling = word.original_word().as_bytes();
+        let ends_in = |letters: Option<u32>, end: Option<&u8>| {
+            letters.is_none_or(|letters| end.is_some_and(|&c| letters & bit(c) != 0))
+        };
+        ends_in(self.first_letters, spelling.first())
+            && ends_in(self.last_letters, spelling.last())
+            && self.some_of.is_none_or(|letters| word.mask & letters != 0)
+            && word.mask & self.none_of == 0
+    }
+}
+
+/// What the word list groups words by, keeping only the first word of each
+/// group, with the others as its other spellings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroupKey {
+    /// The sorted letters, so anagrams are grouped
+    #[default]
+    Anagram,
+
+    /// The sorted letters other than vowels, so words that only differ in
+    /// their vowels are grouped, such as "blast" and "blest". The words in a
+    /// group don't all have the same letters, but the search only uses the
+    /// letters of the first one.
+    Skeleton,
+
+    /// The word itself, so words are only grouped with copies of themselves
+    Exact,
+}
+
+/// Receives progress updates from a running search. The search calls these
+/// methods from several threads at once.
+pub trait ProgressSink: Sync {
+    /// Called before the search starts, with the number of tasks it will
+    /// report through `advance`.
+    fn set_total(&self, n: u64);
+
+    /// Called right after `set_total` by a search whose tasks are pairs of
+    /// words, rather than the single words that it starts from otherwise.
+    /// Does nothing by default.
+    fn tasks_are_pairs(&self) {}
+
+    /// Called when another `n` tasks of the search are done.
+    fn advance(&self, n: u64);
+
+    /// Called with the number of combinations that have been found so far.
+    fn set_found(&self, n: u64);
+
+    /// Called when the search starts on another task, with the words it
+    /// starts from. Several tasks run at once, so this is only the one that
+    /// started last. Does nothing by default.
+    fn set_current(&self, _words: &str) {}
+
+    /// Called when the search is done with the word at index `start`, which it
+    /// started from, with the combinations it found from there. Only a search
+    /// that starts from single words calls this, not one that starts from
+    /// pairs. Does nothing by default.
+    fn finish_start(&self, _start: u32, _combinations: &[Vec<u32>]) {}
+
+    /// Called when a task of the search is done, with the word at index
+    /// `start` that it started from, how long it took, and how many
+    /// combinations it found. A search that starts from pairs has a task for
+    /// each pair, so several of its tasks start from the same word. Does
+    /// nothing by default.
+    fn finish_task(&self, _start: u32, _elapsed: Duration, _found: usize) {}
+
+    /// Called before the search starts on another task. Once this returns
+    /// true, the search skips the tasks it hasn't started yet, and returns
+    /// what it found so far. Returns false by default.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// A `ProgressSink` that ignores all progress updates.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn set_total(&self, _n: u64) {}
+
+    fn advance(&self, _n: u64) {}
+
+    fn set_found(&self, _n: u64) {}
+}
@@ -1,239 +1,2726 @@
-use clap::Parser;
-use core::hash::{Hash, Hasher};
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
-use rayon::prelude::*;
+/// Implements the listed methods of `ProgressSink` by passing them on to the
+/// `ProgressSink` in the given field, for the wrappers around one that only
+/// do something of their own in a few of them, as in
+/// `forward_progress!(progress; set_total, advance)`.
+macro_rules! forward_progress {
+    ($field:ident; $($method:ident),* $(,)?) => {
+        $(forward_progress!(@ $field $method);)*
+    };
+    (@ $field:ident set_total) => {
+        fn set_total(&self, n: u64) {
+            self.$field.set_total(n);
+        }
+    };
+    (@ $field:ident tasks_are_pairs) => {
+        fn tasks_are_pairs(&self) {
+            self.$field.tasks_are_pairs();
+        }
+    };
+    (@ $field:ident advance) => {
+        fn advance(&self, n: u64) {
+            self.$field.advance(n);
+        }
+    };
+    (@ $field:ident set_found) => {
+        fn set_found(&self, n: u64) {
+            self.$field.set_found(n);
+        }
+    };
+    (@ $field:ident set_current) => {
+        fn set_current(&self, words: &str) {
+            self.$field.set_current(words);
+        }
+    };
+    (@ $field:ident finish_start) => {
+        fn finish_start(&self, start: u32, combinations: &[Vec<u32>]) {
+            self.$field.finish_start(start, combinations);
+        }
+    };
+    (@ $field:ident finish_task) => {
+        fn finish_task(&self, start: u32, elapsed: std::time::Duration, found: usize) {
+            self.$field.finish_task(start, elapsed, found);
+        }
+    };
+    (@ $field:ident is_cancelled) => {
+        fn is_cancelled(&self) -> bool {
+            self.$field.is_cancelled()
+        }
+    };
+}
+
+mod checkpoint;
+mod diagnostics;
+mod dot;
+mod html;
+mod jobs;
+mod output;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod progress_log;
+mod queries;
+mod repl;
+mod socket;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use checkpoint::{read_checkpoint, Checkpoint};
+use clap::{Parser, ValueEnum};
+use encoding_rs::Encoding;
+use five_words::{
+    count_by_unused_letter, count_disjoint_pairs, find_greedy, get_difficulties, get_difficulty,
+    get_disjoint_indices, get_top_k_by_coverage, get_words_from_encoded_lists,
+    get_words_from_lists, read_frequencies, read_word_cache, remove_words_without_partners,
+    write_word_cache, Constraints, GroupKey, NoProgress, Options, ProgressSink, Search, Shard,
+    SlotConstraint, Strategy, TreeSize, Word, ECHO_TARGET,
+};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use jobs::JobsOutput;
+use output::{
+    write_aligned, write_json_line, write_sequence, write_sequence_quoted, Columns,
+    LetterPartition, Template,
+};
+use progress_log::ProgressLog;
+use socket::{OnDisconnect, SocketOutput};
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{BufReader, BufWriter, Cursor, IsTerminal, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
 
 /// This program reads a list of lowercase ASCII words, and produces a list of
 /// tab-separated combinations of words that don't have any characters in
 /// common. Any anagrams of words in the list are not considered. It is
 /// inspired by this video: https://www.youtube.com/watch?v=_-AfhLQfb6w
 #[derive(Parser)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "EXIT CODES:\n    0    Combinations were found, or whatever else was asked for was done\n    \
+                  1    Reading or writing something failed\n    \
+                  2    The search ran, but found no combinations\n    \
+                  3    The arguments or the constraints they give aren't valid"
+)]
 struct Args {
-    /// The path to a file with a list of words, or "-" to read from standard input
+    /// The paths to one or more files with lists of words, or "-" to read from
+    /// standard input, which is also what happens if there are none. The lists
+    /// are merged, but --slot-sources can tell them apart.
     #[clap(value_parser)]
-    input_file: std::path::PathBuf,
+    input_files: Vec<PathBuf>,
 
-    /// Show a progress indicator on standard error
-    #[clap(short, long, action, conflicts_with = "verbose")]
+    /// Show a progress indicator on standard error, or on standard output if
+    /// the combinations go somewhere else, such as to a file with --output, to
+    /// the files of --partition-by-first-letter or to --output-socket, which
+    /// leaves standard error to --verbose and --debug
+    #[clap(short, long, action)]
     progress: bool,
 
-    /// Add extra output to standard error, can't be used with a progress bar
-    #[clap(short, long, action, conflicts_with = "progress")]
+    /// Report on the words that are skipped and the combinations that are
+    /// found on standard error, which can only be used with a progress bar
+    /// if the combinations go to a file. RUST_LOG takes precedence, such as
+    /// RUST_LOG=five_words=info.
+    #[clap(short, long, action)]
     verbose: bool,
+
+    /// Report even more than --verbose, such as every word that is added to
+    /// the list and every word that the search starts from
+    #[clap(long, action)]
+    debug: bool,
+
+    /// Check that the search only keeps the words that may still be added to
+    /// a combination, at every step, and stop with a panic if it doesn't.
+    /// This slows the search down, and is meant for working on it
+    #[clap(long, action)]
+    debug_invariants: bool,
+
+    /// Only keep the words at these positions in the word list, counting from
+    /// 0 in the order they were read, such as 1000..2000, to narrow a problem
+    /// down to part of a large list. Anagrams of words outside of the range
+    /// are already left out by then
+    #[clap(long, value_name = "RANGE", value_parser = parse_range)]
+    word_index_range: Option<RangeInclusive<usize>>,
+
+    /// Report every line of the word lists on standard error exactly as it
+    /// was read, with its length and its bytes, to find out why words that
+    /// look right are left out. RUST_LOG takes precedence here as well.
+    #[clap(long, action)]
+    echo_input: bool,
+
+    /// Instead of listing every combination, list the K sets of at most as
+    /// many words as --words that cover the most distinct letters
+    #[clap(long, value_name = "K", value_parser = clap::value_parser!(u64).range(1..))]
+    top_k_by_coverage: Option<u64>,
+
+    /// When to color the output on standard error
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Instead of writing to standard output, write the combinations to one
+    /// file per letter in DIR, by the first letter of their first word
+    #[clap(long, value_name = "DIR", value_parser, conflicts_with_all = &["output", "format"])]
+    partition_by_first_letter: Option<PathBuf>,
+
+    /// Write the combinations to FILE instead of standard output
+    #[clap(short, long, value_name = "FILE", value_parser)]
+    output: Option<PathBuf>,
+
+    /// The format to write the combinations in
+    #[clap(long, value_enum, default_value_t = Format::Tsv)]
+    format: Format,
+
+    /// The line to write for each combination with --format template, with
+    /// {1}, {2} and so on for the words, {words} for all of them separated
+    /// by spaces, {number}, {unused} for the letters the combination leaves
+    /// unused, {letters} for the ones it covers, {letter_map}, {positions}
+    /// and {difficulty}, and {{ and }} for braces, such as "{1} {2} {3} {4}
+    /// {5} (missing {unused})"
+    #[clap(long, value_name = "STR", value_parser, requires = "format")]
+    template: Option<String>,
+
+    /// The encoding of the input, such as "latin1" or "utf-16le" [default: utf-8]
+    #[clap(long, value_name = "ENCODING", value_parser = parse_encoding)]
+    input_encoding: Option<&'static Encoding>,
+
+    /// A word list in an encoding of its own, as ENCODING:PATH, such as
+    /// "latin1:old.txt", which can be given several times. These lists come
+    /// after the ones that are given without an encoding, which are in the
+    /// one of --input-encoding, in the order they are given in
+    #[clap(long, value_name = "ENCODING:PATH", value_parser = parse_encoded_file)]
+    input_file: Vec<EncodedFile>,
+
+    /// The encoding of each of the input files, which is filled in from the
+    /// options rather than parsed
+    #[clap(skip)]
+    input_encodings: Vec<Option<&'static Encoding>>,
+
+    /// What the parallel search starts from
+    #[clap(long, value_enum, default_value_t = StrategyArg::Words)]
+    strategy: StrategyArg,
+
+    /// Number the combinations, starting at 1, in a column in front of them
+    #[clap(short, long, action)]
+    number: bool,
+
+    /// Treat anagrams as different words. Beware that this makes the search
+    /// larger, and that every combination is repeated for each anagram of each
+    /// of its words.
+    #[clap(long, action)]
+    keep_anagrams: bool,
+
+    /// What makes words the same word, of which only the first is kept:
+    /// their letters, their letters other than vowels, or the words
+    /// themselves, which is the same as --keep-anagrams
+    #[clap(
+        long,
+        value_enum,
+        value_name = "KEY",
+        default_value_t = GroupKeyArg::Anagram,
+        conflicts_with = "keep-anagrams"
+    )]
+    group_key: GroupKeyArg,
+
+    /// Skip checking and sorting the words while reading the word list, which
+    /// is a little faster. Only use this on a single list in which every line
+    /// is a word of different lowercase letters and which has no anagrams, or
+    /// the results will be wrong. Lines of other lengths, such as blank ones,
+    /// are still left out.
+    #[clap(long, action, conflicts_with_all = &["new-words", "reference"])]
+    assume_sorted_input: bool,
+
+    /// Read every word on each line of the word lists, separated by spaces,
+    /// tabs or commas, instead of each line as a single word, such as to take
+    /// the words from a text. Punctuation around the words is left out.
+    #[clap(long, action)]
+    tokenize: bool,
+
+    /// Set the options for a common kind of word list at once. The wordle
+    /// preset is the same as --length 5 --words 5 --group-key anagram
+    /// --tokenize, for the lists of Wordle answers and guesses, which are
+    /// also found as JavaScript or JSON arrays such as ["cigar","rebut"]
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with_all = &["length", "words", "group-key", "keep-anagrams", "tokenize"]
+    )]
+    preset: Option<Preset>,
+
+    /// Save the word list to FILE once it's read, and load it from there
+    /// instead of reading the word lists as long as none of them changed
+    /// since, and the options that affect which words are read are the same
+    #[clap(long, value_name = "FILE", value_parser)]
+    word_cache: Option<PathBuf>,
+
+    /// Leave out the words with any of these letters, such as --exclude-letters
+    /// qxz, and only ever look for combinations of the other letters
+    #[clap(long, value_name = "LETTERS", value_parser = parse_letters)]
+    exclude_letters: Option<u32>,
+
+    /// Leave out the words that have a letter in common with WORD, as if its
+    /// letters were given with --exclude-letters, such as to find the
+    /// combinations that go with a word that is already chosen. WORD doesn't
+    /// have to be in the word lists
+    #[clap(long, value_name = "WORD", value_parser = parse_letters)]
+    require_disjoint_from: Option<u32>,
+
+    /// Leave out the words without a vowel, such as "crwth", which keeps the
+    /// combinations pronounceable
+    #[clap(long, action)]
+    require_vowel_in_each: bool,
+
+    /// The letters that count as vowels for --require-vowel-in-each, such as
+    /// --vowels aeiouy to also accept "nymph"
+    #[clap(
+        long,
+        value_name = "LETTERS",
+        value_parser = parse_letters,
+        default_value = "aeiou",
+        requires = "require-vowel-in-each"
+    )]
+    vowels: u32,
+
+    /// Redraw the progress indicator at most once every MS milliseconds, from
+    /// 4 up to 1000 [default: 50]
+    #[clap(
+        long,
+        value_name = "MS",
+        requires = "progress",
+        value_parser = clap::value_parser!(u64).range(4..=1000)
+    )]
+    progress_interval: Option<u64>,
+
+    /// Append a line of JSON to FILE every --progress-log-interval seconds
+    /// while the search runs, with the seconds since it started, the number
+    /// of tasks it's done with, the number of tasks in total, what the tasks
+    /// are, and the number of combinations found so far. The tasks are the
+    /// words that the search starts from, or the pairs of words it starts
+    /// from with --strategy pairs, as the "unit" of each line says
+    #[clap(long, value_name = "FILE", value_parser)]
+    progress_log: Option<PathBuf>,
+
+    /// The number of seconds between the lines of --progress-log
+    #[clap(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        default_value_t = 5,
+        requires = "progress-log"
+    )]
+    progress_log_interval: u64,
+
+    /// Once the search is done, write how long each of its tasks took to
+    /// FILE, to see how evenly the work is spread over the threads. Each line
+    /// has the index of the word the task started from, the word, the thread
+    /// it ran on, the time in seconds and the number of combinations it found
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "pairs-only",
+            "estimate",
+            "profile-hot-words",
+            "degree-histogram",
+            "dump-masks",
+            "explain-failure",
+            "output-socket",
+        ]
+    )]
+    jobs_output: Option<PathBuf>,
+
+    /// Which lists the word in each position has to come from, such as
+    /// "A,B,B,B,B" for a first word from the first list and the rest from the
+    /// second. The lists are called A, B, C and so on, in the order they are
+    /// given in, and a position can allow several lists, like "AB".
+    #[clap(long, value_name = "SLOTS", value_parser = parse_slot_sources)]
+    slot_sources: Option<SlotSources>,
+
+    /// What else the word in each position has to be like, such as
+    /// "start=a-m,has=aeiou,_,not=e+end=s,_" for a first word that starts
+    /// with one of the letters a to m, a second word with a vowel, a fourth
+    /// word without an e that ends in s, and any words in the third and fifth
+    /// positions. The positions are separated by commas, and each one is
+    /// either "_", or constraints separated by "+": start=LETTERS and
+    /// end=LETTERS for the letters the word may start or end with, has=LETTERS
+    /// for letters of which it needs at least one, and not=LETTERS for letters
+    /// it may not have. LETTERS are letters and ranges of them, like "a-dx"
+    #[clap(long, value_name = "SLOTS", value_parser = parse_slot_constraints)]
+    slot_constraints: Option<SlotConstraints>,
+
+    /// Add a column with all the letters that each combination covers, in
+    /// alphabetical order
+    #[clap(long, action)]
+    signature: bool,
+
+    /// Add a column that shows how the words cover the alphabet, with, for
+    /// each letter from a to z, the number of the word that it's in, or a
+    /// dash if it's unused, such as "1-2..." if the first word has an "a" and
+    /// the second a "c"
+    #[clap(long, action)]
+    letter_map: bool,
+
+    /// Add a column that shows how the words are spelled, with, for each
+    /// letter from a to z, its position in the word that has it, or a dash if
+    /// it's unused, such as "3-1..." if a word has an "a" as its third letter
+    /// and a word has a "c" as its first. With --letter-map, this says where
+    /// each letter is in the combination
+    #[clap(long, action)]
+    positions: bool,
+
+    /// The number of megabytes that may be used to precompute which words are
+    /// disjoint with which, or 0 to never do that
+    #[clap(long, value_name = "MB", default_value_t = 1024)]
+    max_memory: u64,
+
+    /// Read the word lists once, and then read commands such as "require
+    /// fjord", "exclude s" and "find" from standard input. Type "help" for a
+    /// list of commands.
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &["top-k-by-coverage", "partition-by-first-letter", "output", "format"]
+    )]
+    interactive: bool,
+
+    /// Read the word lists once, and then run a search for each line of FILE,
+    /// such as "easy: require fjord; exclude qz", and list the combinations
+    /// that each one finds after a line with its label. The label before the
+    /// colon is optional, and the constraints are the "require" and "exclude"
+    /// commands of --interactive, separated by semicolons
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with_all = &["interactive", "top-k-by-coverage", "partition-by-first-letter", "format"]
+    )]
+    queries: Option<PathBuf>,
+
+    /// Give up if nothing arrives on standard input within SECONDS seconds,
+    /// instead of waiting for it forever
+    #[clap(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    stdin_timeout: Option<u64>,
+
+    /// A list of how often words occur, with a word and a count on each line,
+    /// to score the combinations by how rare their words are
+    #[clap(long, value_name = "FILE", value_parser)]
+    frequency_file: Option<PathBuf>,
+
+    /// Add a column with the difficulty of each combination, the sum of
+    /// 1 / (n + 1) over its words, where n is how often the word occurs
+    /// according to --frequency-file
+    #[clap(long, action, requires = "frequency-file")]
+    difficulty: bool,
+
+    /// Write the combinations in order of this key, lowest first, instead of
+    /// in the order they were found in. Combinations with the same key are
+    /// ordered by their words, compared one by one
+    #[clap(long, value_enum, value_name = "KEY")]
+    sort_by: Option<SortKey>,
+
+    /// Instead of listing the combinations, count how many of them leave each
+    /// letter unused, and write a letter and a count on each line
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "partition-by-first-letter",
+            "interactive",
+            "queries",
+            "format",
+            "sort-by",
+        ]
+    )]
+    count_by_unused: bool,
+
+    /// Only search the part I of N of the combinations, with I from 0 up to
+    /// N - 1, to split a search across several processes. Together the N
+    /// shards find every combination exactly once.
+    #[clap(long, value_name = "I/N", value_parser = parse_shard)]
+    shard: Option<Shard>,
+
+    /// Instead of searching from scratch, read partial combinations from FILE,
+    /// one per line with their words separated by whitespace, and list every
+    /// way to complete each of them with words from the word lists
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with_all = &["top-k-by-coverage", "count-by-unused", "interactive", "queries"]
+    )]
+    combine_with: Option<PathBuf>,
+
+    /// Only choose words that are in FILE, a list of words with one on each
+    /// line. The other words are still read, so an allowed word is left out if
+    /// an anagram of it comes first in the word lists.
+    #[clap(long, value_name = "FILE", value_parser)]
+    allow_file: Option<PathBuf>,
+
+    /// Never put two words from the same line of FILE in one combination,
+    /// even though they're disjoint. Each line has two words, separated by
+    /// whitespace.
+    #[clap(long, value_name = "FILE", value_parser)]
+    banned_pairs: Option<PathBuf>,
+
+    /// Ignore the pairs in the file given with --banned-pairs that have a word
+    /// that isn't in the word lists, instead of stopping with an error
+    #[clap(long, action, requires = "banned-pairs")]
+    ignore_unknown_banned_words: bool,
+
+    /// Only list the combinations whose words all have the same pattern of
+    /// vowels and consonants, such as "fjord" and "nymph", where only "aeiou"
+    /// are vowels
+    #[clap(long, action)]
+    same_pattern: bool,
+
+    /// Only list the combinations whose words all start with different
+    /// letters. Words without letters in common always do, so this only
+    /// makes a difference with --letter-budget
+    #[clap(long, action)]
+    distinct_first_letters: bool,
+
+    /// Run the search without writing any combinations, and only write how
+    /// long reading the word lists and searching took, and how many
+    /// combinations were found
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "output",
+            "partition-by-first-letter",
+        ]
+    )]
+    benchmark_mode: bool,
+
+    /// Once the search is done, write to standard error how many states it
+    /// went through, and how many of those were combinations or dead ends,
+    /// which says how well the pruning works regardless of how fast the
+    /// machine is
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "estimate",
+            "profile-hot-words",
+            "degree-histogram",
+            "dump-masks",
+            "explain-failure",
+            "output-socket",
+            "resume",
+        ]
+    )]
+    tree_stats: bool,
+
+    /// Run the search once before the one that is timed, so that it doesn't
+    /// pay for cold caches
+    #[clap(long, action, requires = "benchmark-mode")]
+    warm_up: bool,
+
+    /// Instead of combinations of a number of words, list the sets of any number of
+    /// disjoint words that cover exactly N distinct letters
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=26),
+        conflicts_with_all = &["slot-sources", "slot-constraints", "top-k-by-coverage"]
+    )]
+    letters_covered: Option<u32>,
+
+    /// Instead of combinations of a number of words, list the sets of any
+    /// number of disjoint words that no other word can be added to
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "words",
+            "words-range",
+            "letters-covered",
+            "slot-sources",
+            "slot-constraints",
+            "letter-budget",
+            "banned-pairs",
+            "same-pattern",
+            "top-k-by-coverage",
+            "combine-with",
+            "interactive",
+            "queries",
+            "prefilter",
+        ]
+    )]
+    maximal: bool,
+
+    /// Only list the first combination that starts with each word, for a
+    /// sample of combinations with the most different first words
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &["top-k-by-coverage", "count-by-unused", "pairs-only"]
+    )]
+    one_per_start: bool,
+
+    /// Only list the combinations with at least one word from FILE, a list of
+    /// words that are new compared to the word lists. This is a lot faster
+    /// than searching the whole list again after adding a few words to it.
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with_all = &["slot-sources", "slot-constraints"]
+    )]
+    new_words: Option<PathBuf>,
+
+    /// Read FILE, a larger word list, only to find out which words have
+    /// anagrams, which --show-anagrams then lists. Its words don't end up in
+    /// combinations, unless they're in the word lists as well.
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with = "keep-anagrams"
+    )]
+    reference: Option<PathBuf>,
+
+    /// Split the search into parallel tasks for the first D words of each
+    /// combination, instead of only for the first word. More, smaller tasks
+    /// can keep more threads busy, at the cost of scheduling them.
+    #[clap(
+        long,
+        value_name = "D",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_depth_parallel: u64,
+
+    /// Write the combinations in the opposite order, such as the hardest ones
+    /// first with --sort-by difficulty. All the combinations are collected
+    /// before any of them are written, so this doesn't cost anything extra.
+    #[clap(long, action)]
+    reverse: bool,
+
+    /// Write the words of each combination in alphabetical order, instead of
+    /// in the order of the word list, or of the slots with --slot-sources.
+    /// Sorting the combinations with --sort-by compares the sorted words
+    #[clap(long, action)]
+    sort_words_in_solution: bool,
+
+    /// Instead of reading any word lists, search a small built-in one with a
+    /// known answer, and report whether that answer was found
+    #[clap(long, action)]
+    selftest: bool,
+
+    /// Instead of searching, only count the pairs of words without any
+    /// letters in common, which is quick and gives an idea of how long the
+    /// search would take
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+        ]
+    )]
+    pairs_only: bool,
+
+    /// Instead of searching, write an upper bound on the number of
+    /// combinations, worked out from the words that each word is disjoint
+    /// with. It's far from tight, but it does tell small searches from huge
+    /// ones
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+            "pairs-only",
+            "letter-budget",
+            "letters-covered",
+            "maximal",
+            "words-range",
+            "slot-sources",
+            "slot-constraints",
+        ]
+    )]
+    estimate: bool,
+
+    /// Instead of listing the combinations, list the N words that are
+    /// disjoint with the fewest other words, which are the hardest to place,
+    /// and the N words that are disjoint with the most, with the number of
+    /// words they are disjoint with and the share of the other words that
+    /// choosing them rules out
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+            "pairs-only",
+        ]
+    )]
+    profile_hot_words: Option<u64>,
+
+    /// Instead of listing the combinations, write a table to standard error
+    /// of how many words are disjoint with how many others, in buckets that
+    /// double in size. Many words with few partners make combinations rare
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+            "pairs-only",
+            "estimate",
+            "profile-hot-words",
+        ]
+    )]
+    degree_histogram: bool,
+
+    /// Instead of searching, list the words with the mask of their letters in
+    /// hexadecimal, where bit 0 is "a" and bit 25 is "z", and the mask of the
+    /// letters they don't use
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+            "pairs-only",
+            "profile-hot-words",
+            "degree-histogram",
+        ]
+    )]
+    dump_masks: bool,
+
+    /// Instead of listing the combinations, explain why WORD is or isn't in
+    /// any of them, with the number of words it's disjoint with, and those
+    /// words themselves if there are only a few
+    #[clap(
+        long,
+        value_name = "WORD",
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "partition-by-first-letter",
+            "pairs-only",
+            "profile-hot-words",
+            "degree-histogram",
+            "dump-masks",
+        ]
+    )]
+    explain_failure: Option<String>,
+
+    /// Instead of listing all the combinations, quickly find a single one, by
+    /// covering the letter that the fewest words have first
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "combine-with",
+            "benchmark-mode",
+            "pairs-only",
+            "profile-hot-words",
+            "degree-histogram",
+            "dump-masks",
+            "words-range",
+            "letters-covered",
+            "maximal",
+            "slot-sources",
+            "slot-constraints",
+            "letter-budget",
+            "banned-pairs",
+            "same-pattern",
+            "new-words",
+            "shard",
+            "checkpoint",
+            "resume",
+            "output-socket",
+        ]
+    )]
+    greedy_first: bool,
+
+    /// After the combination that --greedy-first finds, also list all the
+    /// others, as the search finds them without it. The first combination is
+    /// written to standard output as soon as it's found
+    #[clap(
+        long,
+        action,
+        requires = "greedy-first",
+        conflicts_with_all = &["output", "format", "partition-by-first-letter", "number"]
+    )]
+    then_search: bool,
+
+    /// Write each word along with the anagrams of it that were left out of the
+    /// word list, such as "stop/post/pots"
+    #[clap(long, action, conflicts_with = "keep-anagrams")]
+    show_anagrams: bool,
+
+    /// What to put between a word and its anagrams with --show-anagrams
+    #[clap(
+        long,
+        value_name = "STR",
+        default_value = "/",
+        value_parser = parse_anagram_separator,
+        requires = "show-anagrams"
+    )]
+    anagram_separator: String,
+
+    /// Instead of listing the combinations, list the words that aren't part of
+    /// any of them, one per line
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "partition-by-first-letter",
+            "format",
+        ]
+    )]
+    unused_words: bool,
+
+    /// Instead of listing the combinations, write how many different sets of
+    /// letters they cover between them
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "unused-words",
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "partition-by-first-letter",
+            "format",
+        ]
+    )]
+    distinct_signatures: bool,
+
+    /// Instead of listing the combinations, write how many there are, how many
+    /// different sets of letters they cover, how long the search took, and
+    /// how many combinations leave each letter unused
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = &[
+            "unused-words",
+            "distinct-signatures",
+            "top-k-by-coverage",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "partition-by-first-letter",
+            "format",
+            "output-socket",
+        ]
+    )]
+    summary_only: bool,
+
+    /// The number of letters that the words may have, either a single number
+    /// or a range such as 4..=6 or 4..7 [default: 5]
+    #[clap(long, value_name = "RANGE", value_parser = parse_length, alias = "include-length")]
+    length: Option<RangeInclusive<usize>>,
+
+    /// The number of words in each combination
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..=26),
+        default_value_t = 5,
+        conflicts_with = "letters-covered"
+    )]
+    words: u64,
+
+    /// Search for combinations of each number of words in RANGE, such as
+    /// 3..=5, instead of only for --words, and write the number of words of
+    /// each combination in front of it
+    #[clap(
+        long,
+        value_name = "RANGE",
+        value_parser = parse_words_range,
+        conflicts_with_all = &[
+            "words",
+            "letters-covered",
+            "slot-sources",
+            "slot-constraints",
+            "top-k-by-coverage",
+            "combine-with",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+        ]
+    )]
+    words_range: Option<RangeInclusive<usize>>,
+
+    /// Every --checkpoint-every words that the search is done starting from,
+    /// append those words and the combinations found from them to FILE, so
+    /// that --resume can pick up from there if the search is interrupted
+    #[clap(
+        long,
+        value_name = "FILE",
+        value_parser,
+        conflicts_with_all = &[
+            "top-k-by-coverage",
+            "combine-with",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "words-range",
+        ]
+    )]
+    checkpoint: Option<PathBuf>,
+
+    /// The number of words that the search finishes between writes to the
+    /// checkpoint file
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        default_value_t = 100,
+        requires = "checkpoint"
+    )]
+    checkpoint_every: u64,
+
+    /// Skip the words that the checkpoint file says the search is done with,
+    /// and list the combinations it found from them along with the new ones.
+    /// Without a checkpoint file, the search starts from the beginning.
+    #[clap(long, action, requires = "checkpoint")]
+    resume: bool,
+
+    /// Instead of writing the combinations when the search is done, stream
+    /// them to the Unix domain socket or named pipe at PATH as they are
+    /// found. If there is nothing at PATH yet, create a socket there and wait
+    /// for a consumer to connect to it.
+    #[clap(
+        long,
+        value_name = "PATH",
+        value_parser,
+        conflicts_with_all = &[
+            "output",
+            "top-k-by-coverage",
+            "combine-with",
+            "count-by-unused",
+            "interactive",
+            "queries",
+            "benchmark-mode",
+            "pairs-only",
+            "words-range",
+            "checkpoint",
+            "unused-words",
+            "distinct-signatures",
+            "dedup-anagram-solutions",
+            "partition-by-first-letter",
+            "sort-by",
+            "reverse",
+            "sort-words-in-solution",
+            "format",
+        ]
+    )]
+    output_socket: Option<PathBuf>,
+
+    /// What to do when the consumer of --output-socket disconnects before the
+    /// search is done
+    #[clap(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        default_value = "stop",
+        requires = "output-socket"
+    )]
+    on_disconnect: OnDisconnect,
+
+    /// Let the words in a combination share up to K letters in total, instead
+    /// of none, where a letter that three words have counts twice
+    #[clap(
+        long,
+        value_name = "K",
+        value_parser = clap::value_parser!(u32).range(0..=26),
+        conflicts_with_all = &["top-k-by-coverage", "pairs-only"]
+    )]
+    letter_budget: Option<u32>,
+
+    /// Before searching, remove the words that have no letters in common with
+    /// any other word, which can't be part of a combination. --verbose reports
+    /// how many there are.
+    #[clap(long, action, conflicts_with_all = &["top-k-by-coverage", "letters-covered"])]
+    prefilter: bool,
+
+    /// Write the file names of the word lists that each word, or an anagram
+    /// of it, appears in after it, such as "fjord (common.txt, obscure.txt)"
+    #[clap(long, action)]
+    show_source: bool,
+
+    /// Only list the first of the combinations that have the same letters in
+    /// each word, which are the ones that only differ in anagrams. That only
+    /// happens with --keep-anagrams, which otherwise lists every combination
+    /// once for each anagram of each of its words.
+    #[clap(long, action, requires = "keep-anagrams")]
+    dedup_anagram_solutions: bool,
 }
 
-struct Word {
-    word: [u8; 5],
-    original_word: String,
+/// For each position in a combination, the set of lists that the word in that
+/// position can come from.
+#[derive(Clone)]
+struct SlotSources(Vec<u32>);
+
+/// Parses a comma-separated list of slots, each of which is one or more list
+/// letters, into a set of lists per slot.
+fn parse_slot_sources(slots: &str) -> Result<SlotSources, String> {
+    slots
+        .split(',')
+        .map(|slot| {
+            if slot.is_empty() {
+                return Err(String::from("every slot needs at least one list"));
+            }
+            slot.bytes().try_fold(0, |sources, list| match list {
+                b'A'..=b'Z' => Ok(sources | 1 << (list - b'A')),
+                _ => Err(format!(
+                    "lists are called A through Z, not {}",
+                    list as char
+                )),
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(SlotSources)
 }
 
-impl Hash for Word {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        // Only consider the sorted bytes when hashing the Word, because we're
-        // also storing the original word. It's not desirable for that to be
-        // part of the hash, because otherwise we would be storing all the
-        // anagrams of this Word in the set, too.
-        self.word.hash(state);
+/// For each position in a combination, the constraints on the word in that
+/// position.
+#[derive(Clone)]
+struct SlotConstraints(Vec<SlotConstraint>);
+
+/// Parses a comma-separated list of slots, each of which is "_" or
+/// constraints separated by "+", as described for --slot-constraints.
+fn parse_slot_constraints(slots: &str) -> Result<SlotConstraints, String> {
+    slots
+        .split(',')
+        .map(|slot| {
+            let mut constraint = SlotConstraint::default();
+            if slot == "_" {
+                return Ok(constraint);
+            }
+            for part in slot.split('+') {
+                let (name, letters) = part
+                    .split_once('=')
+                    .ok_or_else(|| format!("{:?} should be a name, an = and letters", part))?;
+                let letters = parse_letter_ranges(letters)?;
+                match name {
+                    "start" => constraint.first_letters = Some(letters),
+                    "end" => constraint.last_letters = Some(letters),
+                    "has" => constraint.some_of = Some(letters),
+                    "not" => constraint.none_of |= letters,
+                    _ => {
+                        return Err(format!(
+                            "a slot can have start, end, has and not constraints, not {}",
+                            name
+                        ))
+                    }
+                }
+            }
+            Ok(constraint)
+        })
+        .collect::<Result<_, _>>()
+        .map(SlotConstraints)
+}
+
+/// Parses letters and ranges of letters, such as "a-dx", into a mask.
+fn parse_letter_ranges(letters: &str) -> Result<u32, String> {
+    if letters.is_empty() {
+        return Err(String::from("a constraint needs at least one letter"));
+    }
+    let mut bytes = letters.bytes().peekable();
+    let mut mask = 0;
+    while let Some(first) = bytes.next() {
+        let last = match bytes.next_if_eq(&b'-') {
+            Some(_) => bytes
+                .next()
+                .ok_or_else(|| format!("{} ends in a dash", letters))?,
+            None => first,
+        };
+        if let Some(&c) = [first, last].iter().find(|c| !c.is_ascii_lowercase()) {
+            return Err(format!("{} is not a lowercase letter", c as char));
+        }
+        if first > last {
+            return Err(format!(
+                "the range {}-{} is empty",
+                first as char, last as char
+            ));
+        }
+        mask |= (first..=last).fold(0, |mask, c| mask | 1 << (c - b'a'));
+    }
+    Ok(mask)
+}
+
+/// Checks that the anagram separator can be told apart from the tabs between
+/// the columns.
+fn parse_anagram_separator(separator: &str) -> Result<String, String> {
+    if separator.is_empty() {
+        return Err(String::from("the separator can't be empty"));
+    }
+    if separator.contains('\t') {
+        return Err(String::from(
+            "the separator can't have a tab in it, because that separates the columns",
+        ));
+    }
+    Ok(String::from(separator))
+}
+
+/// Parses a number, or a range of them in Rust's syntax, where "A..B" leaves
+/// out B and "A..=B" doesn't.
+fn parse_range(range: &str) -> Result<RangeInclusive<usize>, String> {
+    let number = |n: &str| {
+        n.parse::<usize>()
+            .map_err(|e| format!("{} is not a number: {}", n, e))
+    };
+    let parsed = if let Some((start, end)) = range.split_once("..=") {
+        number(start)?..=number(end)?
+    } else if let Some((start, end)) = range.split_once("..") {
+        let end = number(end)?;
+        if end == 0 {
+            return Err(format!("the range {} is empty", range));
+        }
+        number(start)?..=end - 1
+    } else {
+        let n = number(range)?;
+        n..=n
+    };
+    if parsed.is_empty() {
+        return Err(format!("the range {} is empty", range));
+    }
+    Ok(parsed)
+}
+
+/// Parses a word length, or a range of them, as for `parse_range`.
+fn parse_length(length: &str) -> Result<RangeInclusive<usize>, String> {
+    let range = parse_range(length)?;
+    if *range.start() == 0 || *range.end() > 26 {
+        return Err(String::from(
+            "words have at least 1 and at most 26 different letters",
+        ));
+    }
+    Ok(range)
+}
+
+/// Parses a number of words in a combination, or a range of them, as for
+/// `parse_range`.
+fn parse_words_range(words: &str) -> Result<RangeInclusive<usize>, String> {
+    let range = parse_range(words)?;
+    if *range.start() == 0 || *range.end() > 26 {
+        return Err(String::from(
+            "a combination has at least 1 and at most 26 words",
+        ));
+    }
+    Ok(range)
+}
+
+/// Parses a set of lowercase letters into a mask.
+fn parse_letters(letters: &str) -> Result<u32, String> {
+    match letters.chars().find(|c| !c.is_ascii_lowercase()) {
+        Some(c) => Err(format!("{} is not a lowercase letter", c)),
+        None => Ok(letters.bytes().fold(0, |mask, c| mask | 1 << (c - b'a'))),
+    }
+}
+
+/// Parses a shard in the form "I/N".
+fn parse_shard(shard: &str) -> Result<Shard, String> {
+    let (index, count) = shard
+        .split_once('/')
+        .ok_or_else(|| format!("a shard looks like I/N, such as 0/4, not {}", shard))?;
+    let index: usize = index.parse().map_err(|e| format!("{}: {}", index, e))?;
+    let count: usize = count.parse().map_err(|e| format!("{}: {}", count, e))?;
+    if count == 0 {
+        return Err(String::from("there has to be at least one shard"));
     }
+    if index >= count {
+        return Err(format!(
+            "there is no shard {} of {}, they go from 0 up to {}",
+            index,
+            count,
+            count - 1
+        ));
+    }
+    Ok(Shard { index, count })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GroupKeyArg {
+    /// Group anagrams
+    Anagram,
+
+    /// Group words with the same letters other than vowels
+    Skeleton,
+
+    /// Don't group words
+    Exact,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StrategyArg {
+    /// Start one task from each word
+    Words,
+
+    /// Find all disjoint pairs first, and start one task from each pair
+    Pairs,
+}
+
+/// Looks up an encoding by one of its WHATWG labels, such as "latin1".
+fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| format!("unknown encoding: {}", label))
+}
+
+/// A word list with the encoding to read it in.
+#[derive(Clone)]
+struct EncodedFile {
+    encoding: &'static Encoding,
+    path: PathBuf,
+}
+
+/// Parses an encoding and a path, separated by the first colon.
+fn parse_encoded_file(file: &str) -> Result<EncodedFile, String> {
+    let (label, path) = file.split_once(':').ok_or_else(|| {
+        format!(
+            "{} should be an encoding and a path, such as latin1:{}",
+            file, file
+        )
+    })?;
+    Ok(EncodedFile {
+        encoding: parse_encoding(label)?,
+        path: PathBuf::from(path),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One combination per line, with the words separated by tabs
+    Tsv,
+
+    /// The same as tsv, but with every backslash, tab, newline and carriage
+    /// return in a word, such as in the names of the lists with --show-source,
+    /// written as \\, \t, \n and \r
+    TsvQuoted,
+
+    /// One combination per line, with each column padded with spaces to its
+    /// widest value, so that the columns line up in a terminal
+    Aligned,
+
+    /// A web page with a table of the combinations, and a box to filter them
+    Html,
+
+    /// One JSON object per line, with the words of a combination and the
+    /// letters it leaves unused, along with the extra columns
+    Ndjson,
+
+    /// A line per combination as --template describes it
+    Template,
+
+    /// A GraphViz graph with a node for each combination and for each word
+    /// in any of them, and an edge between each combination and its words
+    DotMatches,
+
+    /// An SQLite database with a table of words and a table of combinations,
+    /// which requires --output. The rows are written once the search is done
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// A Parquet file with a column per word and a column with the letters
+    /// that each combination leaves unused, which requires --output
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Preset {
+    /// Five-letter words, five to a combination, one spelling of each set of
+    /// letters, and the words read wherever they are on a line
+    Wordle,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    /// The difficulty of the combination, which needs --frequency-file
+    Difficulty,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    Always,
+    Never,
+    Auto,
 }
 
-impl PartialEq for Word {
-    // Only consider the sorted bytes when comparing Words, because it's
-    // desireable for anagrams of the original words to be equal, not
-    // different.
-    fn eq(&self, other: &Self) -> bool {
-        self.word == other.word
+impl ColorChoice {
+    /// Resolves `Auto` to either `Always` or `Never`, depending on whether
+    /// standard error is a terminal.
+    fn resolve(self) -> Self {
+        match self {
+            ColorChoice::Auto if std::io::stderr().is_terminal() => ColorChoice::Always,
+            ColorChoice::Auto => ColorChoice::Never,
+            choice => choice,
+        }
     }
 }
 
-impl Eq for Word {}
+impl Args {
+    /// Returns the letters that no word may have, from --exclude-letters and
+    /// --require-disjoint-from.
+    fn excluded_letters(&self) -> u32 {
+        self.exclude_letters.unwrap_or(0) | self.require_disjoint_from.unwrap_or(0)
+    }
+
+    /// Returns `true` if the combinations go somewhere other than standard
+    /// output, such as a file, a directory or a socket, so that the progress
+    /// bar can go there instead.
+    fn leaves_stdout_free(&self) -> bool {
+        self.output.is_some()
+            || self.partition_by_first_letter.is_some()
+            || self.output_socket.is_some()
+    }
 
-impl Word {
-    fn new(word: [u8; 5], original_word: String) -> Self {
-        Word {
-            word,
-            original_word,
+    fn options(&self) -> Options {
+        Options {
+            encoding: self.input_encoding,
+            strategy: match self.strategy {
+                StrategyArg::Words => Strategy::Words,
+                StrategyArg::Pairs => Strategy::Pairs,
+            },
+            keep_anagrams: self.keep_anagrams,
+            group_key: match self.group_key {
+                GroupKeyArg::Anagram => GroupKey::Anagram,
+                GroupKeyArg::Skeleton => GroupKey::Skeleton,
+                GroupKeyArg::Exact => GroupKey::Exact,
+            },
+            slot_sources: self.slot_sources.as_ref().map(|slots| slots.0.clone()),
+            slot_constraints: self.slot_constraints.as_ref().map(|slots| slots.0.clone()),
+            max_memory: Some(self.max_memory * 1_000_000),
+            shard: self.shard,
+            // Reading the allowed words can fail, so that's up to `run`.
+            allowed_words: None,
+            letters_covered: self.letters_covered,
+            maximal: self.maximal,
+            one_per_start: self.one_per_start,
+            same_pattern: self.same_pattern,
+            distinct_first_letters: self.distinct_first_letters,
+            debug_invariants: self.debug_invariants,
+            // The number of new words is only known once they're read.
+            starts_before: None,
+            max_depth_parallel: self.max_depth_parallel.try_into().unwrap_or(usize::MAX),
+            word_lengths: self.length.clone(),
+            // The banned pairs are indices, which need the word list.
+            banned_pairs: HashSet::new(),
+            skip_starts: HashSet::new(),
+            letter_budget: self.letter_budget,
+            assume_sorted_input: self.assume_sorted_input,
+            tokenize: self.tokenize,
+            excluded_letters: self.excluded_letters(),
+            vowels: self.require_vowel_in_each.then_some(self.vowels),
         }
     }
+}
+
+/// Sends the diagnostics of the library to standard error, as much of them as
+/// RUST_LOG asks for, or otherwise as --verbose and --debug ask for.
+fn init_tracing(args: &Args) {
+    let default = if args.debug {
+        "five_words=debug"
+    } else if args.verbose {
+        "five_words=info"
+    } else {
+        "warn"
+    };
+    let default = match args.echo_input {
+        true => format!("{},{}=info", default, ECHO_TARGET),
+        false => String::from(default),
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(args.color == ColorChoice::Always)
+        .event_format(diagnostics::Diagnostics)
+        .init();
+}
+
+/// Reports the progress of the search on an indicatif progress bar.
+struct IndicatifProgress(ProgressBar);
+
+impl ProgressSink for IndicatifProgress {
+    fn set_total(&self, n: u64) {
+        self.0.set_length(n);
+    }
+
+    fn advance(&self, n: u64) {
+        self.0.inc(n);
+    }
+
+    fn set_found(&self, n: u64) {
+        self.0.set_message(format!("{} found", n));
+    }
+
+    fn set_current(&self, words: &str) {
+        self.0.set_prefix(format!(", at {}", words));
+    }
+}
 
-    /// Returns `true` if the two `Word`s do not have any characters in common.
-    /// This function assumes that `word` is sorted for both `Word`s.
-    #[allow(clippy::comparison_chain)]
-    fn is_disjoint_with(&self, other: &Self) -> bool {
-        let mut a = 0;
-        let mut b = 0;
+/// Returns a progress bar, which is hidden unless a progress bar was asked for.
+/// Its length is set by the search once that knows how much work there is.
+fn progress_bar(args: &Args) -> ProgressBar {
+    if !args.progress {
+        return ProgressBar::hidden();
+    }
+    let template = if args.color == ColorChoice::Always {
+        "{elapsed_precise:.dim} {wide_bar:.cyan/blue} {percent:.bold}% {msg}{prefix:.dim}"
+    } else {
+        "{elapsed_precise} {wide_bar} {percent}% {msg}{prefix}"
+    };
+    // Without --progress-interval, this is as often as indicatif redraws by
+    // default.
+    let hz = args
+        .progress_interval
+        .map_or(20, |ms| (1000 / ms).try_into().unwrap());
+    let target = match args.leaves_stdout_free() {
+        true => ProgressDrawTarget::stdout_with_hz(hz),
+        false => ProgressDrawTarget::stderr_with_hz(hz),
+    };
+    // The message is the number of combinations found so far, and the prefix
+    // the word that the search started from last.
+    ProgressBar::with_draw_target(Some(0), target)
+        .with_style(ProgressStyle::default_bar().template(template).unwrap())
+        .with_message("0 found")
+}
 
+/// Reads all of standard input. If there is a timeout, it's an error if the
+/// first bytes don't arrive in time, but after that the rest may take as long
+/// as it takes.
+fn read_stdin(timeout: Option<Duration>) -> std::io::Result<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+
+    // The thread is left blocked on standard input if the timeout passes, but
+    // then the program is about to exit anyway.
+    thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
         loop {
-            if a == 5 || b == 5 {
+            let mut buffer = vec![0; 64 * 1024];
+            let result = stdin.read(&mut buffer).map(|n| {
+                buffer.truncate(n);
+                buffer
+            });
+            let done = !matches!(&result, Ok(chunk) if !chunk.is_empty());
+            if sender.send(result).is_err() || done {
                 break;
             }
-            if self.word[a] == other.word[b] {
-                return false;
-            } else if self.word[a] < other.word[b] {
-                a += 1;
-            } else {
-                b += 1;
+        }
+    });
+
+    let first = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("nothing arrived on standard input within {:?}", timeout),
+            )
+        })?,
+        None => receiver.recv().unwrap(),
+    };
+    let mut bytes = first?;
+    for chunk in receiver {
+        bytes.extend(chunk?);
+    }
+    Ok(bytes)
+}
+
+/// Returns the names of the word lists for --show-source, in the order they
+/// are read, which is the same as for the sources of the words.
+fn source_names(args: &Args) -> Vec<String> {
+    args.input_files
+        .iter()
+        .chain(&args.new_words)
+        .chain(&args.reference)
+        .map(|path| match path.file_name() {
+            _ if path.as_os_str() == "-" => String::from("stdin"),
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => path.display().to_string(),
+        })
+        .collect()
+}
+
+/// Reads the word lists, the new words and the reference list, in that order.
+fn read_word_list(
+    args: &Args,
+    options: &Options,
+    stdin_is_terminal: bool,
+) -> std::io::Result<Vec<Word>> {
+    let stdin_timeout = args.stdin_timeout.map(Duration::from_secs);
+    let mut input_readers: Vec<(Box<dyn Read>, _)> = vec![];
+    for (input_file, &encoding) in args.input_files.iter().zip(&args.input_encodings) {
+        if input_file.as_os_str() == "-" {
+            if stdin_is_terminal {
+                eprintln!("Reading the word list from the terminal, end it with Ctrl-D.");
             }
+            input_readers.push((Box::new(Cursor::new(read_stdin(stdin_timeout)?)), encoding));
+        } else {
+            input_readers.push((Box::new(File::open(input_file)?), encoding));
         }
-        true
     }
+    if let Some(path) = &args.new_words {
+        input_readers.push((Box::new(File::open(path)?), options.encoding));
+    }
+    if let Some(path) = &args.reference {
+        input_readers.push((Box::new(File::open(path)?), options.encoding));
+    }
+    get_words_from_encoded_lists(input_readers, options)
+}
+
+/// Returns the files that the word list is read from.
+fn word_list_files(args: &Args) -> Vec<&PathBuf> {
+    args.input_files
+        .iter()
+        .chain(&args.new_words)
+        .chain(&args.reference)
+        .collect()
 }
 
-/// Returns `true` if the array has no duplicate values. This function assumes
-/// that `word` is sorted.
-fn all_characters_unique(word: &[u8]) -> bool {
-    for i in 1..word.len() {
-        if word[i - 1] == word[i] {
-            return false;
+/// Returns what tells apart everything that changes which words are read,
+/// and how.
+fn word_list_key(args: &Args, options: &Options) -> String {
+    format!(
+        "{:?} {:?} {:?} {} {:?} {} {} {} {:?} {:?}",
+        word_list_files(args),
+        args.input_encodings
+            .iter()
+            .map(|encoding| encoding.map(Encoding::name))
+            .collect::<Vec<_>>(),
+        options.word_lengths,
+        options.keep_anagrams,
+        options.group_key,
+        options.tokenize,
+        options.assume_sorted_input,
+        options.excluded_letters,
+        options.vowels,
+        options.encoding.map(Encoding::name),
+    )
+}
+
+/// Returns what a checkpoint file has to have been written for to resume
+/// from it: the word list, and everything that changes which combinations the
+/// search finds in it.
+fn checkpoint_key(args: &Args, options: &Options, sequence_length: usize) -> String {
+    format!(
+        "{} {:?} {} {:?} {:?} {:?} {:?} {} {} {} {} {:?} {:?} {:?} {}",
+        word_list_key(args, options),
+        args.word_index_range,
+        sequence_length,
+        options.letter_budget,
+        options.letters_covered,
+        options.slot_sources,
+        options.slot_constraints,
+        options.maximal,
+        options.one_per_start,
+        options.same_pattern,
+        options.distinct_first_letters,
+        options.shard,
+        args.allow_file,
+        args.banned_pairs,
+        args.ignore_unknown_banned_words,
+    )
+}
+
+/// Loads the word list from the cache at `path` if it's newer than all the
+/// files it was read from and it was read with the same options, and
+/// otherwise reads the word lists and writes the cache.
+fn read_cached_word_list(
+    path: &Path,
+    args: &Args,
+    options: &Options,
+) -> std::io::Result<Vec<Word>> {
+    let key = word_list_key(args, options);
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified());
+    if let Ok(cached) = modified(path) {
+        let mut fresh = true;
+        for file in word_list_files(args) {
+            fresh &= modified(file)? <= cached;
+        }
+        if fresh {
+            match read_word_cache(BufReader::new(File::open(path)?), &key) {
+                Ok(Some(word_list)) => {
+                    info!(
+                        words = word_list.len(),
+                        "loaded the word list from the cache"
+                    );
+                    return Ok(word_list);
+                }
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, "reading the word lists again, the cache is damaged"),
+            }
         }
     }
-    true
+
+    let word_list = read_word_list(args, options, false)?;
+    write_word_cache(BufWriter::new(File::create(path)?), &key, &word_list)?;
+    info!(words = word_list.len(), "wrote the word list to the cache");
+    Ok(word_list)
 }
 
-fn get_disjoint_indices(
-    word_list: &Vec<Word>,
-    sequence_length: usize,
+/// Removes every combination of which an earlier one has the same sets of
+/// letters, regardless of the order of the words.
+fn dedup_anagram_solutions(sequences: &mut Vec<Vec<u32>>, word_list: &[Word]) {
+    let mut seen = HashSet::new();
+    sequences.retain(|sequence| {
+        let mut masks: Vec<u32> = sequence
+            .iter()
+            .map(|&i| word_list[i as usize].mask())
+            .collect();
+        masks.sort_unstable();
+        seen.insert(masks)
+    });
+}
+
+/// Puts the combinations, and the words in them, in the order that --sort-by,
+/// --reverse and --sort-words-in-solution ask for.
+fn order_sequences(
     args: &Args,
-) -> Vec<Vec<usize>> {
-    let word_list_len = word_list.len();
-    let bar = if args.progress {
-        ProgressBar::new(word_list_len.try_into().unwrap()).with_style(
-            ProgressStyle::default_bar()
-                .template("{elapsed_precise} {wide_bar} {percent}%")
-                .unwrap(),
+    sequences: &mut [Vec<u32>],
+    word_list: &[Word],
+    difficulties: Option<&[f64]>,
+) {
+    if args.sort_words_in_solution {
+        for sequence in sequences.iter_mut() {
+            sequence.sort_by_key(|&i| word_list[i as usize].original_word());
+        }
+    }
+    if let Some(key) = args.sort_by {
+        sort_sequences(sequences, key, word_list, difficulties);
+    }
+    if args.reverse {
+        sequences.reverse();
+    }
+}
+
+/// Sorts the combinations by the key, and the ones that are tied by their
+/// words, so that the order doesn't depend on the order they were found in.
+fn sort_sequences(
+    sequences: &mut [Vec<u32>],
+    key: SortKey,
+    word_list: &[Word],
+    difficulties: Option<&[f64]>,
+) {
+    match key {
+        SortKey::Difficulty => {
+            let difficulties = difficulties.expect("sorting by difficulty needs difficulties");
+            sequences.sort_by(|a, b| {
+                get_difficulty(a, difficulties)
+                    .total_cmp(&get_difficulty(b, difficulties))
+                    .then_with(|| compare_words(a, b, word_list))
+            })
+        }
+    }
+}
+
+/// Compares two combinations by their first words, then by their second words
+/// and so on, where a combination that runs out of words first comes first.
+fn compare_words(a: &[u32], b: &[u32], word_list: &[Word]) -> Ordering {
+    let word = |&i: &u32| word_list[i as usize].original_word();
+    a.iter().map(word).cmp(b.iter().map(word))
+}
+
+/// Writes the `n` words with the fewest partners and the `n` words with the
+/// most, each with their number of partners and the share of the other words
+/// that they rule out. Words with as many partners as each other stay in the
+/// order of the list.
+fn write_hot_words<W: Write>(
+    out: &mut W,
+    word_list: &[Word],
+    partners: &[u32],
+    n: usize,
+) -> std::io::Result<()> {
+    let mut fewest: Vec<usize> = (0..word_list.len()).collect();
+    fewest.sort_by_key(|&i| partners[i]);
+    let mut most = fewest.clone();
+    most.sort_by_key(|&i| Reverse(partners[i]));
+    let others = word_list.len().saturating_sub(1).max(1) as f64;
+    for (heading, order) in [("Fewest partners:", fewest), ("Most partners:", most)] {
+        writeln!(out, "{}", heading)?;
+        for i in order.into_iter().take(n) {
+            let ruled_out = 1.0 - partners[i] as f64 / others;
+            writeln!(
+                out,
+                "{}\t{}\t{:.1}%",
+                word_list[i].original_word(),
+                partners[i],
+                100.0 * ruled_out
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The width of the longest bar of `write_degree_histogram`.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Writes how many words have how many partners, with a line per bucket: 0,
+/// 1, 2-3, 4-7 and so on up to the bucket of the most partners, each with the
+/// number of words and a bar as long as that number relative to the largest
+/// bucket.
+fn write_degree_histogram<W: Write>(out: &mut W, partners: &[u32]) -> std::io::Result<()> {
+    // Bucket 0 is for no partners, and bucket b for 2^(b-1) up to 2^b - 1.
+    let bucket = |count: u32| (u32::BITS - count.leading_zeros()) as usize;
+    let mut counts = vec![0usize; bucket(partners.iter().copied().max().unwrap_or(0)) + 1];
+    for &count in partners {
+        counts[bucket(count)] += 1;
+    }
+    let largest = counts.iter().copied().max().unwrap_or(0).max(1);
+    writeln!(out, "Partners	Words")?;
+    for (b, &words) in counts.iter().enumerate() {
+        let range = match b {
+            0 | 1 => b.to_string(),
+            _ => format!("{}-{}", 1u64 << (b - 1), (1u64 << b) - 1),
+        };
+        let bar = "#".repeat((words * HISTOGRAM_WIDTH).div_ceil(largest));
+        writeln!(out, "{}	{}	{}", range, words, bar)?;
+    }
+    Ok(())
+}
+
+/// Writes the number of disjoint pairs, the average number of partners, and an
+/// upper bound on the number of combinations. A combination is found from its
+/// first word, and its other words are all partners of that word that come
+/// after it, so there are no more combinations that start with a word than
+/// there are ways to choose the other words from those partners.
+fn write_estimate<W: Write>(
+    out: &mut W,
+    search: &Search,
+    word_list: &[Word],
+    sequence_length: usize,
+) -> std::io::Result<()> {
+    let mut pairs = 0;
+    let mut bound = 0.0;
+    for i in 0..word_list.len() as u32 {
+        let later = search.partners(i).iter().filter(|&&j| j > i).count() as u64;
+        pairs += later;
+        bound += binomial(later, sequence_length as u64 - 1);
+    }
+    writeln!(
+        out,
+        "There are {} disjoint pairs among {} words, and a word is disjoint with {:.1} others on \
+         average.",
+        pairs,
+        word_list.len(),
+        2.0 * pairs as f64 / word_list.len().max(1) as f64
+    )?;
+    // Beyond what an f64 holds exactly, the digits are made up anyway.
+    if bound < 1e15 {
+        writeln!(
+            out,
+            "There are at most {} combinations of {} words.",
+            bound.round(),
+            sequence_length
         )
     } else {
-        ProgressBar::hidden()
-    };
-
-    let result = (0..word_list_len)
-        .into_par_iter()
-        .progress_with(bar)
-        .map(|i| {
-            get_disjoint_indices_partial(
-                word_list,
-                sequence_length,
-                args.verbose,
-                vec![],
-                vec![i],
-                &(0..word_list_len).collect(),
-            )
-        });
+        writeln!(
+            out,
+            "There are at most {:.3e} combinations of {} words.",
+            bound, sequence_length
+        )
+    }
+}
 
-    result.flatten().collect()
+/// Returns the number of ways to choose `k` out of `n` things, as a float,
+/// since it can get very large.
+fn binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k.min(n - k)).fold(1.0, |ways, i| ways * (n - i) as f64 / (i + 1) as f64)
 }
 
-#[allow(clippy::ptr_arg)]
-fn get_disjoint_indices_partial(
-    word_list: &Vec<Word>,
+/// The most partners of a word that --explain-failure lists.
+const MAX_LISTED_PARTNERS: usize = 20;
+
+/// Writes how many words the word at index `i` is disjoint with, and whether
+/// that's enough for a combination, and if it is, whether any of them are
+/// disjoint with each other too.
+fn explain_failure<W: Write>(
+    out: &mut W,
+    search: &Search,
+    word_list: &[Word],
     sequence_length: usize,
-    verbose: bool,
-    mut partial: Vec<Vec<usize>>,
-    mut state: Vec<usize>,
-    valid_indices: &Vec<usize>,
-) -> Vec<Vec<usize>> {
-    // Found a match. Further down this function, all the combinations of words
-    // that are not disjoint are filtered out. This means that non-disjoint
-    // combinations are not considered at all, so if the state has the desired
-    // length then it is guaranteed to be pairwise disjoint.
-    if state.len() == sequence_length {
-        if verbose {
-            eprint!("Found:");
-            for i in state.iter() {
-                eprint!(" {}", word_list[*i].original_word);
-            }
-            eprintln!();
-        }
-        partial.push(state);
-        return partial;
-    }
-
-    // First, prune all words in the valid indices that are disjoint with the
-    // last word in the state. This is done here so the calling function
-    // get_disjoint_indices doesn't have to do it.
-    let last_index = *state.last().expect("state must not be empty");
-    let new_valid_indices: Vec<usize> = valid_indices
-        .iter()
-        .filter(|&i| word_list[last_index].is_disjoint_with(&word_list[*i]))
-        .cloned()
-        .collect();
-
-    // The filter is here because otherwise there would be duplicate results.
-    for next_index in new_valid_indices.iter().filter(|&i| *i >= last_index) {
-        state.push(*next_index);
-        partial = get_disjoint_indices_partial(
-            word_list,
+    i: u32,
+) -> std::io::Result<()> {
+    let word = word_list[i as usize].original_word();
+    let partners = search.partners(i);
+    writeln!(out, "{} is disjoint with {} words.", word, partners.len())?;
+    if partners.len() <= MAX_LISTED_PARTNERS {
+        for &j in &partners {
+            writeln!(out, "\t{}", word_list[j as usize].original_word())?;
+        }
+    }
+
+    let constraints = Constraints {
+        required: vec![i],
+        ..Constraints::default()
+    };
+    let found = search.find(&constraints, &NoProgress).len();
+    if found > 0 {
+        writeln!(out, "It is in {} combinations.", found)?;
+    } else if partners.len() + 1 < sequence_length {
+        writeln!(
+            out,
+            "That is too few for a combination of {} words, which needs {} others.",
             sequence_length,
-            verbose,
-            partial,
-            state.clone(),
-            &new_valid_indices,
-        );
-        state.pop();
+            sequence_length - 1
+        )?;
+    } else {
+        writeln!(
+            out,
+            "It isn't in any combination of {} words, because no {} of those words are \
+             disjoint with each other as well.",
+            sequence_length,
+            sequence_length - 1
+        )?;
     }
+    Ok(())
+}
 
-    partial
+/// Opens the file given with --output, or standard output.
+fn open_output(args: &Args) -> std::io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    })
 }
 
-fn get_words<T: Read>(mut input_reader: T, args: &Args) -> std::io::Result<Vec<Word>> {
-    let mut word_set: HashSet<Word> = HashSet::new();
-    let mut input = String::new();
+/// Returns the index of the word in the list, or of an anagram of it if the
+/// word itself isn't in there.
+fn find_word(word_list: &[Word], word: &str) -> Result<u32, String> {
+    if let Some(i) = word_list.iter().position(|w| w.original_word() == word) {
+        return Ok(i as u32);
+    }
+    let mask = word
+        .parse::<Word>()
+        .map_err(|e| format!("{} is not a valid word: {}", word, e))?
+        .mask();
+    word_list
+        .iter()
+        .position(|w| w.mask() == mask)
+        .map(|i| i as u32)
+        .ok_or_else(|| format!("{} is not in the word list", word))
+}
 
-    input_reader.read_to_string(&mut input)?;
+/// The word list for --selftest. Besides words that don't fit anywhere, it
+/// has a duplicate, words with repeated letters, and an uppercase word, all of
+/// which should be left out.
+const SELFTEST_WORDS: &str = "\
+fjord\ngucks\nnymph\nvibex\nwaltz\nchunk\nglyph\nstops\nspots\nhello\nQUICK\ncrwth\n\
+jumpy\nbrick\ndwarf\nfjord\nquack\nzones\nmixed\n";
 
-    for line in input.lines().filter(|l| l.len() == 5) {
-        let mut bytes = line.as_bytes().to_vec();
-        bytes.sort();
+/// The only combination in `SELFTEST_WORDS`, as it should be written.
+const SELFTEST_ANSWER: &str = "fjord\tgucks\tnymph\tvibex\twaltz\n";
 
-        if !all_characters_unique(&bytes) {
-            continue;
+/// Reads the built-in word list, searches it with each strategy, and checks
+/// that the output is the known answer.
+fn selftest() -> std::io::Result<()> {
+    let started = Instant::now();
+    for strategy in [Strategy::Words, Strategy::Pairs] {
+        let options = Options {
+            strategy,
+            ..Options::default()
+        };
+        let word_list = get_words_from_lists([SELFTEST_WORDS.as_bytes()], &options)?;
+        let sequences = get_disjoint_indices(&word_list, 5, &options, &NoProgress);
+        let mut out = vec![];
+        for (i, sequence) in sequences.iter().enumerate() {
+            write_sequence(&mut out, &Columns::default(), i + 1, sequence, &word_list)?;
         }
+        let out = String::from_utf8_lossy(&out);
+        if out != SELFTEST_ANSWER {
+            return Err(std::io::Error::other(format!(
+                "the self-test failed, it should have found {:?} but found {:?}",
+                SELFTEST_ANSWER, out
+            )));
+        }
+    }
+    writeln!(
+        std::io::stdout(),
+        "The self-test passed in {:.3} s.",
+        started.elapsed().as_secs_f64()
+    )
+}
 
-        let word = Word::new(bytes.clone().try_into().unwrap(), String::from(line));
+/// Reads partial combinations from a file, with the words of each one on a
+/// line, separated by whitespace, and checks that their words are disjoint.
+fn read_partials(
+    path: &Path,
+    word_list: &[Word],
+    sequence_length: usize,
+) -> std::io::Result<Vec<Vec<u32>>> {
+    let input = std::fs::read_to_string(path)?;
+    let mut partials = vec![];
+    for (number, line) in input.lines().enumerate() {
+        let invalid = |message: String| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line {} of {}: {}", number + 1, path.display(), message),
+            )
+        };
+        let partial = line
+            .split_whitespace()
+            .map(|word| find_word(word_list, word))
+            .collect::<Result<Vec<u32>, String>>()
+            .map_err(invalid)?;
+        if partial.is_empty() {
+            continue;
+        }
+        if partial.len() > sequence_length {
+            return Err(invalid(format!(
+                "{} words are too many, a combination has {}",
+                partial.len(),
+                sequence_length
+            )));
+        }
+        let mut mask = 0;
+        for &i in &partial {
+            if mask & word_list[i as usize].mask() != 0 {
+                return Err(invalid(format!(
+                    "{} shares letters with the words before it",
+                    word_list[i as usize].original_word()
+                )));
+            }
+            mask |= word_list[i as usize].mask();
+        }
+        partials.push(partial);
+    }
+    Ok(partials)
+}
 
-        // This check is not strictly necessary to insert the Word, but it's
-        // here because of the verbose output, to debug the anagram logic.
-        if word_set.contains(&word) {
-            if args.verbose {
-                let existing = word_set.get(&word).unwrap();
-                eprintln!(
-                    "An anagram of the word {} is already in the list ({}).",
-                    word.original_word, existing.original_word
-                );
+/// Reads pairs of words that may not be in the same combination from a file,
+/// with two words on each line, separated by whitespace. A pair with a word
+/// that isn't in the list is an error, unless `ignore_unknown` is set.
+fn read_banned_pairs(
+    path: &Path,
+    word_list: &[Word],
+    ignore_unknown: bool,
+) -> std::io::Result<HashSet<(u32, u32)>> {
+    let input = std::fs::read_to_string(path)?;
+    let mut banned_pairs = HashSet::new();
+    for (number, line) in input.lines().enumerate() {
+        let invalid = |message: String| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line {} of {}: {}", number + 1, path.display(), message),
+            )
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (a, b) = match words[..] {
+            [] => continue,
+            [a, b] => (a, b),
+            _ => {
+                return Err(invalid(format!(
+                    "a banned pair should be two words, not {}",
+                    words.len()
+                )))
             }
-        } else {
-            if args.verbose {
-                eprintln!("Adding the word {} to the list.", word.original_word);
+        };
+        match (find_word(word_list, a), find_word(word_list, b)) {
+            (Ok(a), Ok(b)) => {
+                banned_pairs.insert((a.min(b), a.max(b)));
             }
-            word_set.insert(word);
+            (Err(message), _) | (_, Err(message)) if ignore_unknown => {
+                warn!(
+                    "ignoring line {} of {}: {}",
+                    number + 1,
+                    path.display(),
+                    message
+                );
+            }
+            (Err(message), _) | (_, Err(message)) => return Err(invalid(message)),
         }
     }
-    Ok(word_set.into_iter().collect())
+    Ok(banned_pairs)
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
 
-    let word_list = if args.input_file == PathBuf::from("-") {
-        get_words(std::io::stdin(), &args)?
-    } else {
-        let input_file = File::open(args.input_file.clone())?;
-        get_words(input_file, &args)?
+/// How a run that didn't fail ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// What was asked for was done, such as listing the combinations
+    Done,
+
+    /// The search ran, but there were no combinations to list
+    NoCombinations,
+}
+
+/// Returns how a run that looked for combinations ended, given whether it
+/// found none.
+fn outcome_of(none_found: bool) -> Outcome {
+    match none_found {
+        true => Outcome::NoCombinations,
+        false => Outcome::Done,
+    }
+}
+
+/// The exit codes, which tell a script how the run ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Exit {
+    /// Combinations were found, or whatever else was asked for was done
+    Success = 0,
+
+    /// Reading or writing something failed
+    Io = 1,
+
+    /// The search ran, but found no combinations
+    NoCombinations = 2,
+
+    /// The arguments aren't valid, the constraints they give can't be
+    /// searched for, or a file that they name, such as the banned pairs or a
+    /// checkpoint, doesn't have what it should
+    InvalidInput = 3,
+}
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) if e.use_stderr() => {
+            let _ = e.print();
+            return ExitCode::from(Exit::InvalidInput as u8);
+        }
+        // This is --help or --version.
+        Err(e) => e.exit(),
     };
+    let exit = match run(args) {
+        Ok(Outcome::Done) => Exit::Success,
+        Ok(Outcome::NoCombinations) => Exit::NoCombinations,
+        // Whatever reads the output, such as head, stopped reading it, which
+        // is its way of saying it has seen enough.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Exit::Success,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            match e.kind() {
+                std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => {
+                    Exit::InvalidInput
+                }
+                _ => Exit::Io,
+            }
+        }
+    };
+    ExitCode::from(exit as u8)
+}
+
+fn run(mut args: Args) -> std::io::Result<Outcome> {
+    args.color = args.color.resolve();
+
+    // The progress bar draws through the console crate, which otherwise makes
+    // up its own mind about colors.
+    console::set_colors_enabled_stderr(args.color == ColorChoice::Always);
+    console::set_colors_enabled(args.color == ColorChoice::Always);
+    init_tracing(&args);
+    if args.selftest {
+        return selftest().map(|()| Outcome::Done);
+    }
+
+    match args.preset {
+        Some(Preset::Wordle) => {
+            args.length = Some(5..=5);
+            args.words = 5;
+            args.group_key = GroupKeyArg::Anagram;
+            args.tokenize = true;
+        }
+        None => {}
+    }
+
+    args.input_encodings = vec![args.input_encoding; args.input_files.len()];
+    for file in std::mem::take(&mut args.input_file) {
+        args.input_files.push(file.path);
+        args.input_encodings.push(Some(file.encoding));
+    }
 
-    for sequence in get_disjoint_indices(&word_list, 5, &args).iter() {
-        for i in 0..5 {
-            if i > 0 {
-                print!("\t");
+    let stdin_is_terminal = std::io::stdin().is_terminal();
+    if args.input_files.is_empty() {
+        // Waiting for someone to type a word list is almost never what was
+        // meant, so it takes an explicit "-".
+        if stdin_is_terminal && !args.interactive {
+            return Err(invalid_input(String::from(
+                "no word list was given. Give the path to one, such as \
+                 \"five-words words_alpha.txt\", pipe one into standard input, or use \"-\" \
+                 to type one in. Run \"five-words --help\" for more options.",
+            )));
+        }
+        args.input_files.push(PathBuf::from("-"));
+        args.input_encodings.push(args.input_encoding);
+    }
+
+    let sequence_length = args.words as usize;
+    let sequence_lengths = args
+        .words_range
+        .clone()
+        .unwrap_or(sequence_length..=sequence_length);
+    // The words of a combination have no letters in common, so they need at
+    // least as many different letters as the shortest words have in total.
+    let shortest = args.length.as_ref().map_or(5, |lengths| *lengths.start());
+    // With a letter budget, that many of those letters may be the same.
+    let longest_sequence = *sequence_lengths.end();
+    let letters =
+        (longest_sequence * shortest).saturating_sub(args.letter_budget.unwrap_or(0) as usize);
+    // Sets of letters or maximal sets have as many words as fit, which is as
+    // many of the shortest words as there are letters for, counting the ones
+    // that the budget lets them share.
+    let most_words = match (args.letters_covered, args.maximal) {
+        (Some(covered), _) => (covered + args.letter_budget.unwrap_or(0)) as usize / shortest,
+        (None, true) => 26 / shortest,
+        (None, false) => longest_sequence,
+    };
+    let template = match (&args.template, args.format) {
+        (Some(template), Format::Template) => {
+            Some(Template::parse(template, most_words).map_err(invalid_input)?)
+        }
+        (None, Format::Template) => {
+            return Err(invalid_input(String::from(
+                "the template format needs a template, given with --template",
+            )))
+        }
+        (Some(_), _) => {
+            return Err(invalid_input(String::from(
+                "--template is only used with --format template",
+            )))
+        }
+        (None, _) => None,
+    };
+    let alphabet = 26 - args.excluded_letters().count_ones() as usize;
+    if args.letters_covered.is_none() && !args.maximal && letters > alphabet {
+        return Err(invalid_input(format!(
+            "there can't be any combinations of {} words of {} letters or more, which would \
+             have at least {} different letters, since {}",
+            longest_sequence,
+            shortest,
+            letters,
+            match alphabet {
+                26 => String::from("the alphabet only has 26"),
+                _ => format!("only {} letters aren't excluded", alphabet),
             }
-            print!("{}", word_list[sequence[i]].original_word);
+        )));
+    }
+
+    if let Some(SlotSources(slots)) = &args.slot_sources {
+        let lists = (1 << args.input_files.len()) - 1;
+        if slots.len() != sequence_length {
+            return Err(invalid_input(format!(
+                "--slot-sources needs {} slots, one for each word, not {}",
+                sequence_length,
+                slots.len()
+            )));
+        }
+        if slots.iter().any(|sources| sources & !lists != 0) {
+            return Err(invalid_input(format!(
+                "--slot-sources refers to a list that wasn't given, there are only {}",
+                args.input_files.len()
+            )));
         }
-        println!();
     }
-    Ok(())
+
+    if let Some(SlotConstraints(slots)) = &args.slot_constraints {
+        if slots.len() != sequence_length {
+            return Err(invalid_input(format!(
+                "--slot-constraints needs {} slots, one for each word, not {}",
+                sequence_length,
+                slots.len()
+            )));
+        }
+    }
+
+    if args.prefilter && *sequence_lengths.start() < 2 {
+        return Err(invalid_input(String::from(
+            "--prefilter removes the words that can't be combined with another word, so it \
+             needs combinations of at least two words",
+        )));
+    }
+
+    if args.output_socket.is_some() && args.strategy == StrategyArg::Pairs {
+        return Err(invalid_input(String::from(
+            "--output-socket writes the combinations once the search is done with the word \
+             they start with, so it only works with --strategy words",
+        )));
+    }
+
+    if args.checkpoint.is_some() && args.strategy == StrategyArg::Pairs {
+        return Err(invalid_input(String::from(
+            "--checkpoint keeps track of the words that the search starts from, so it only \
+             works with --strategy words",
+        )));
+    }
+
+    if args.progress && (args.verbose || args.debug) && !args.leaves_stdout_free() {
+        return Err(invalid_input(String::from(
+            "--progress and the diagnostics of --verbose and --debug would both go to \
+             standard error, unless the combinations go somewhere else than standard \
+             output, such as with --output, --partition-by-first-letter or --output-socket",
+        )));
+    }
+
+    if args.group_key == GroupKeyArg::Exact && (args.reference.is_some() || args.show_anagrams) {
+        return Err(invalid_input(String::from(
+            "--group-key exact keeps every word, so there are no other spellings for \
+             --reference or --show-anagrams to find",
+        )));
+    }
+
+    if args.word_cache.is_some() && args.input_files.iter().any(|f| f.as_os_str() == "-") {
+        return Err(invalid_input(String::from(
+            "--word-cache can't tell whether standard input changed, so it only works with \
+             word lists in files",
+        )));
+    }
+
+    if args.assume_sorted_input && args.input_files.len() > 1 {
+        return Err(invalid_input(String::from(
+            "--assume-sorted-input doesn't check for words that are in more than one list, \
+             so it only works with a single word list",
+        )));
+    }
+
+    if args.interactive && args.input_files.iter().any(|f| f.as_os_str() == "-") {
+        return Err(invalid_input(String::from(
+            "--interactive reads commands from standard input, so it can't read a word list \
+             from there as well",
+        )));
+    }
+
+    let mut options = args.options();
+    if let Some(path) = &args.allow_file {
+        let allowed = std::fs::read_to_string(path)?;
+        options.allowed_words = Some(allowed.lines().map(|l| String::from(l.trim())).collect());
+    }
+    let started = Instant::now();
+    let mut word_list = match &args.word_cache {
+        Some(path) => read_cached_word_list(path, &args, &options)?,
+        None => read_word_list(&args, &options, stdin_is_terminal)?,
+    };
+    let reference_list = 1 << (args.input_files.len() + args.new_words.is_some() as usize);
+    if args.reference.is_some() {
+        // The reference list is read last, so that its spellings only ever
+        // end up as anagrams of the words from the other lists. The words
+        // that are only in there have no place in the search.
+        word_list.retain(|word| word.sources() != reference_list);
+    }
+    if let Some(range) = &args.word_index_range {
+        if *range.end() >= word_list.len() {
+            return Err(invalid_input(format!(
+                "the word index range goes up to {}, but the word list has {} words",
+                range.end(),
+                word_list.len()
+            )));
+        }
+        word_list.truncate(range.end() + 1);
+        word_list.drain(..range.start());
+    }
+    if args.prefilter {
+        let removed = remove_words_without_partners(&mut word_list, &options);
+        info!(
+            removed,
+            "removed the words that no other word is disjoint with"
+        );
+    }
+    if args.new_words.is_some() {
+        // The new words go first, so that every combination with a new word
+        // starts with one, and the search only has to start from those.
+        let new_list = 1 << args.input_files.len();
+        let (mut new, old): (Vec<Word>, Vec<Word>) = word_list
+            .into_iter()
+            .partition(|word| word.sources() & !reference_list == new_list);
+        options.starts_before = Some(new.len());
+        new.extend(old);
+        word_list = new;
+    }
+    if let Some(path) = &args.banned_pairs {
+        options.banned_pairs =
+            read_banned_pairs(path, &word_list, args.ignore_unknown_banned_words)?;
+    }
+    let reading_time = started.elapsed();
+
+    let difficulties = match &args.frequency_file {
+        Some(path) => Some(get_difficulties(
+            &word_list,
+            &read_frequencies(File::open(path)?)?,
+        )),
+        None if args.sort_by == Some(SortKey::Difficulty) => {
+            return Err(invalid_input(String::from(
+                "sorting by difficulty needs a list of word frequencies, given with \
+                 --frequency-file",
+            )));
+        }
+        None => None,
+    };
+    if template.as_ref().is_some_and(Template::needs_difficulty) && difficulties.is_none() {
+        return Err(invalid_input(String::from(
+            "{difficulty} in the template needs a list of word frequencies, given with \
+             --frequency-file",
+        )));
+    }
+    let columns = Columns {
+        number: args.number,
+        signature: args.signature,
+        letter_map: args.letter_map,
+        positions: args.positions,
+        word_count: args.words_range.is_some() || args.maximal,
+        difficulty: difficulties.clone().filter(|_| args.difficulty),
+        anagram_separator: args.show_anagrams.then(|| args.anagram_separator.clone()),
+        sources: args.show_source.then(|| source_names(&args)),
+    };
+    if args.interactive {
+        return repl::run(
+            &args,
+            &word_list,
+            &options,
+            &columns,
+            difficulties.as_deref(),
+        )
+        .map(|()| Outcome::Done);
+    }
+
+    if let Some(path) = &args.queries {
+        return queries::run(
+            &mut open_output(&args)?,
+            path,
+            &args,
+            &word_list,
+            &options,
+            &columns,
+            difficulties.as_deref(),
+        )
+        .map(|()| Outcome::Done);
+    }
+
+    if args.pairs_only {
+        let mut out = open_output(&args)?;
+        writeln!(
+            out,
+            "There are {} disjoint pairs among {} words.",
+            count_disjoint_pairs(&word_list),
+            word_list.len()
+        )?;
+        return out.flush().map(|()| Outcome::Done);
+    }
+
+    if args.estimate {
+        let search = Search::new(&word_list, sequence_length, &options);
+        let mut out = open_output(&args)?;
+        write_estimate(&mut out, &search, &word_list, sequence_length)?;
+        return out.flush().map(|()| Outcome::Done);
+    }
+
+    if let Some(n) = args.profile_hot_words {
+        let search = Search::new(&word_list, sequence_length, &options);
+        let mut out = open_output(&args)?;
+        write_hot_words(&mut out, &word_list, &search.partner_counts(), n as usize)?;
+        return out.flush().map(|()| Outcome::Done);
+    }
+
+    if args.degree_histogram {
+        let search = Search::new(&word_list, sequence_length, &options);
+        let mut err = std::io::stderr().lock();
+        write_degree_histogram(&mut err, &search.partner_counts())?;
+        return err.flush().map(|()| Outcome::Done);
+    }
+
+    if let Some(word) = &args.explain_failure {
+        let i = find_word(&word_list, word).map_err(invalid_input)?;
+        let search = Search::new(&word_list, sequence_length, &options);
+        let mut out = open_output(&args)?;
+        explain_failure(&mut out, &search, &word_list, sequence_length, i)?;
+        return out.flush().map(|()| Outcome::Done);
+    }
+
+    if args.dump_masks {
+        let mut out = open_output(&args)?;
+        for word in &word_list {
+            writeln!(
+                out,
+                "{}\t{:07x}\t{:07x}",
+                word.original_word(),
+                word.mask(),
+                !word.mask() & ((1 << 26) - 1)
+            )?;
+        }
+        return out.flush().map(|()| Outcome::Done);
+    }
+
+    let bar = IndicatifProgress(progress_bar(&args));
+    let jobs = args.jobs_output.as_ref().map(|_| JobsOutput::new(&bar));
+    let progress: &dyn ProgressSink = match &jobs {
+        Some(jobs) => jobs,
+        None => &bar,
+    };
+    let write_jobs = || match (&jobs, &args.jobs_output) {
+        (Some(jobs), Some(path)) => jobs.write(path, &word_list),
+        _ => Ok(()),
+    };
+    let log = match &args.progress_log {
+        Some(path) => Some(ProgressLog::new(
+            path,
+            Duration::from_secs(args.progress_log_interval),
+            progress,
+        )?),
+        None => None,
+    };
+    let progress: &dyn ProgressSink = match &log {
+        Some(log) => log,
+        None => progress,
+    };
+    if args.benchmark_mode {
+        if args.warm_up {
+            get_disjoint_indices(&word_list, sequence_length, &options, &NoProgress);
+        }
+        let started = Instant::now();
+        let found = get_disjoint_indices(&word_list, sequence_length, &options, progress).len();
+        let search_time = started.elapsed();
+        bar.0.finish();
+        write_jobs()?;
+        let mut out = std::io::stdout().lock();
+        writeln!(
+            out,
+            "Read {} words in {:.3} s.",
+            word_list.len(),
+            reading_time.as_secs_f64()
+        )?;
+        writeln!(
+            out,
+            "Found {} combinations in {:.3} s.",
+            found,
+            search_time.as_secs_f64()
+        )?;
+        return out.flush().map(|()| Outcome::Done);
+    }
+    if args.count_by_unused {
+        let (counts, len) = count_by_unused_letter(&word_list, sequence_length, &options, progress);
+        bar.0.finish();
+        let mut out = open_output(&args)?;
+        for (letter, count) in (b'a'..=b'z').zip(counts) {
+            writeln!(out, "{}\t{}", letter as char, count)?;
+        }
+        return out.flush().map(|()| outcome_of(len == 0));
+    }
+
+    if let Some(path) = &args.output_socket {
+        let output = SocketOutput::new(path, args.on_disconnect, &word_list, &columns, progress)?;
+        let found = get_disjoint_indices(&word_list, sequence_length, &options, &output);
+        bar.0.finish();
+        return output.finish().map(|()| outcome_of(found.is_empty()));
+    }
+
+    let greedy = match args.greedy_first {
+        true => find_greedy(&word_list, sequence_length, &options),
+        false => None,
+    };
+    if args.greedy_first {
+        let mut out = open_output(&args)?;
+        if let Some(sequence) = &greedy {
+            write_sequence(&mut out, &columns, 1, sequence, &word_list)?;
+        }
+        out.flush()?;
+        match (args.then_search, &greedy) {
+            (true, _) => {}
+            (false, Some(_)) => return Ok(Outcome::Done),
+            (false, None) => return Ok(Outcome::NoCombinations),
+        }
+    }
+
+    let search_started = Instant::now();
+    let mut tree = TreeSize::default();
+    let mut sequences = match (args.top_k_by_coverage, &args.combine_with) {
+        (Some(k), _) => get_top_k_by_coverage(
+            &word_list,
+            sequence_length,
+            k.try_into().unwrap(),
+            &options,
+            progress,
+        ),
+        (None, Some(path)) => {
+            let partials = read_partials(path, &word_list, sequence_length)?;
+            let search = Search::new(&word_list, sequence_length, &options);
+            let mut sequences = vec![];
+            for partial in partials {
+                let constraints = Constraints {
+                    required: partial,
+                    ..Constraints::default()
+                };
+                sequences.extend(search.find(&constraints, progress));
+            }
+            tree = search.tree_size();
+            sequences
+        }
+        (None, None) if args.words_range.is_some() => {
+            // The words that are disjoint with each word don't depend on the
+            // number of words, so they are only worked out once.
+            let mut search = Search::new(&word_list, sequence_length, &options);
+            let mut sequences = vec![];
+            for sequence_length in sequence_lengths {
+                search.set_sequence_length(sequence_length);
+                bar.0.set_position(0);
+                sequences.extend(search.find(&Constraints::default(), progress));
+            }
+            tree = search.tree_size();
+            sequences
+        }
+        (None, None) if args.checkpoint.is_some() => {
+            let path = args.checkpoint.as_ref().unwrap();
+            let key = checkpoint_key(&args, &options, sequence_length);
+            let mut sequences = vec![];
+            let mut keep = 0;
+            if args.resume && path.exists() {
+                // Sets of letters and maximal sets have any number of words.
+                let len =
+                    (args.letters_covered.is_none() && !args.maximal).then_some(sequence_length);
+                let resumed = read_checkpoint(path, &key, len, &word_list)?;
+                options.skip_starts = resumed.done;
+                sequences = resumed.sequences;
+                keep = resumed.len;
+            }
+            let checkpoint = Checkpoint::new(
+                path,
+                keep,
+                &key,
+                args.checkpoint_every as usize,
+                &word_list,
+                progress,
+            )?;
+            let search = Search::new(&word_list, sequence_length, &options);
+            sequences.extend(search.find(&Constraints::default(), &checkpoint));
+            tree = search.tree_size();
+            checkpoint.finish()?;
+            // Every combination starts with the word it was found from, so
+            // this puts the ones from the checkpoint file back where they
+            // would have been without the interruption.
+            sequences.sort_by_key(|sequence| sequence[0]);
+            sequences
+        }
+        (None, None) => {
+            let search = Search::new(&word_list, sequence_length, &options);
+            let sequences = search.find(&Constraints::default(), progress);
+            tree = search.tree_size();
+            sequences
+        }
+    };
+    let search_time = search_started.elapsed();
+    bar.0.finish();
+    if args.tree_stats {
+        let combinations = sequences.len() as u64;
+        eprintln!(
+            "The search went through {} states, of which {} were leaves: {} combinations and \
+             {} dead ends.",
+            tree.nodes,
+            tree.leaves,
+            combinations,
+            tree.leaves.saturating_sub(combinations)
+        );
+    }
+    // This writes the last line of the progress log.
+    drop(log);
+    write_jobs()?;
+    if let Some(greedy) = &greedy {
+        sequences.retain(|sequence| sequence != greedy);
+    }
+
+    if args.dedup_anagram_solutions {
+        dedup_anagram_solutions(&mut sequences, &word_list);
+    }
+
+    let outcome = outcome_of(sequences.is_empty() && greedy.is_none());
+    if args.unused_words {
+        let mut used = vec![false; word_list.len()];
+        for &i in sequences.iter().flatten() {
+            used[i as usize] = true;
+        }
+        let mut out = open_output(&args)?;
+        for (word, _) in word_list.iter().zip(used).filter(|(_, used)| !used) {
+            writeln!(out, "{}", columns.word(word))?;
+        }
+        return out.flush().map(|()| outcome);
+    }
+
+    let mask_of = |sequence: &Vec<u32>| {
+        sequence
+            .iter()
+            .fold(0, |mask, &i| mask | word_list[i as usize].mask())
+    };
+    if args.distinct_signatures {
+        let signatures: HashSet<u32> = sequences.iter().map(mask_of).collect();
+        let mut out = open_output(&args)?;
+        writeln!(out, "{}", signatures.len())?;
+        return out.flush().map(|()| outcome);
+    }
+    if args.summary_only {
+        let signatures: HashSet<u32> = sequences.iter().map(mask_of).collect();
+        let mut unused = [0u64; 26];
+        for mask in sequences.iter().map(mask_of) {
+            for (letter, count) in unused.iter_mut().enumerate() {
+                if mask & 1 << letter == 0 {
+                    *count += 1;
+                }
+            }
+        }
+        let mut out = open_output(&args)?;
+        writeln!(out, "Combinations: {}", sequences.len())?;
+        writeln!(out, "Distinct signatures: {}", signatures.len())?;
+        writeln!(out, "Search time: {:.3} s", search_time.as_secs_f64())?;
+        writeln!(out, "Combinations per unused letter:")?;
+        for (letter, count) in (b'a'..=b'z').zip(unused) {
+            writeln!(out, "{}\t{}", letter as char, count)?;
+        }
+        return out.flush().map(|()| outcome);
+    }
+
+    order_sequences(&args, &mut sequences, &word_list, difficulties.as_deref());
+
+    if let Some(dir) = &args.partition_by_first_letter {
+        let mut partition = LetterPartition::new(dir)?;
+        for (i, sequence) in sequences.iter().enumerate() {
+            let letter = word_list[sequence[0] as usize].original_word().as_bytes()[0];
+            write_sequence(
+                partition.writer(letter)?,
+                &columns,
+                i + 1,
+                sequence,
+                &word_list,
+            )?;
+        }
+        partition.flush()?;
+        return Ok(outcome);
+    }
+
+    match args.format {
+        Format::Tsv => {
+            let mut out = open_output(&args)?;
+            for (i, sequence) in sequences.iter().enumerate() {
+                write_sequence(&mut out, &columns, i + 1, sequence, &word_list)?;
+            }
+            out.flush()?;
+        }
+        Format::TsvQuoted => {
+            let mut out = open_output(&args)?;
+            for (i, sequence) in sequences.iter().enumerate() {
+                write_sequence_quoted(&mut out, &columns, i + 1, sequence, &word_list)?;
+            }
+            out.flush()?;
+        }
+        Format::Aligned => {
+            let mut out = open_output(&args)?;
+            write_aligned(&mut out, &columns, &sequences, &word_list)?;
+            out.flush()?;
+        }
+        Format::Ndjson => {
+            let mut out = open_output(&args)?;
+            for (i, sequence) in sequences.iter().enumerate() {
+                write_json_line(&mut out, &columns, i + 1, sequence, &word_list)?;
+            }
+            out.flush()?;
+        }
+        Format::Template => {
+            let template = template.as_ref().expect("the template was checked");
+            let mut out = open_output(&args)?;
+            for (i, sequence) in sequences.iter().enumerate() {
+                template.write(
+                    &mut out,
+                    &columns,
+                    i + 1,
+                    sequence,
+                    &word_list,
+                    difficulties.as_deref(),
+                )?;
+            }
+            out.flush()?;
+        }
+        Format::DotMatches => {
+            let mut out = open_output(&args)?;
+            dot::write_matches(&mut out, &columns, &sequences, &word_list)?;
+            out.flush()?;
+        }
+        Format::Html => {
+            let mut out = open_output(&args)?;
+            html::write_page(&mut out, &columns, &sequences, &word_list)?;
+            out.flush()?;
+        }
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => {
+            let path = args.output.as_ref().ok_or_else(|| {
+                invalid_input(String::from(
+                    "the sqlite format needs a file to write to, given with --output",
+                ))
+            })?;
+            sqlite::write_database(path, &word_list, &sequences)?;
+        }
+        #[cfg(feature = "parquet")]
+        Format::Parquet => {
+            let path = args.output.as_ref().ok_or_else(|| {
+                invalid_input(String::from(
+                    "the parquet format needs a file to write to, given with --output",
+                ))
+            })?;
+            parquet::write_file(path, &word_list, &sequences)?;
+        }
+    }
+    Ok(outcome)
 }
@@ -0,0 +1,636 @@
+//! Writing the combinations that were found.
+
+use five_words::{get_difficulty, letters_of, Word};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The columns that go on each line, besides the words themselves.
+#[derive(Default)]
+pub struct Columns {
+    /// The number of the combination, starting at 1, in front of the words
+    pub number: bool,
+
+    /// The number of words in the combination, after the number
+    pub word_count: bool,
+
+    /// All the letters that the words cover, in alphabetical order
+    pub signature: bool,
+
+    /// For each letter from a to z, which of the words it's in, as described
+    /// for `letter_map`
+    pub letter_map: bool,
+
+    /// For each letter from a to z, where it is in the word that has it, as
+    /// described for `positions`
+    pub positions: bool,
+
+    /// The difficulty of the combination, if there are difficulties for the
+    /// words in the list
+    pub difficulty: Option<Vec<f64>>,
+
+    /// What to put between the spellings of a word and its anagrams, if the
+    /// anagrams should be shown at all
+    pub anagram_separator: Option<String>,
+
+    /// The names of the word lists, in the order they were read, if each word
+    /// should be followed by the ones it appears in
+    pub sources: Option<Vec<String>>,
+}
+
+impl Columns {
+    /// Returns how the word is written, which is its spelling, followed by
+    /// those of its anagrams if those are shown, and then by the names of the
+    /// lists it appears in if those are shown.
+    pub fn word<'a>(&self, word: &'a Word) -> Cow<'a, str> {
+        let spellings = self.spellings(word);
+        match &self.sources {
+            Some(names) => {
+                let sources: Vec<&str> = names
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| word.sources() & 1 << i != 0)
+                    .map(|(_, name)| name.as_str())
+                    .collect();
+                Cow::Owned(format!("{} ({})", spellings, sources.join(", ")))
+            }
+            None => spellings,
+        }
+    }
+
+    /// Returns, for each letter from a to z, the number of the word of the
+    /// sequence that it's in, such as "1" for the first word, or a dash if
+    /// none of them have it. The words after the ninth have letters for
+    /// numbers, from "a" for the tenth. If several words have a letter, this
+    /// is the first of them.
+    pub fn letter_map(sequence: &[u32], word_list: &[Word]) -> String {
+        (0..26)
+            .map(|letter| {
+                sequence
+                    .iter()
+                    .position(|&i| word_list[i as usize].mask() & 1 << letter != 0)
+                    .map_or('-', |position| {
+                        char::from_digit(position as u32 + 1, 36).unwrap()
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns, for each letter from a to z, its position in the word of the
+    /// sequence that has it, such as "1" if it's the first letter of that
+    /// word, or a dash if none of them have it. The positions after the ninth
+    /// have letters for numbers, as in `letter_map`. Together with the letter
+    /// map, this says how the words are spelled, and not only which letters
+    /// they have.
+    pub fn positions(sequence: &[u32], word_list: &[Word]) -> String {
+        (b'a'..=b'z')
+            .map(|letter| {
+                sequence
+                    .iter()
+                    .find_map(|&i| word_list[i as usize].position_of(letter))
+                    .map_or('-', |position| {
+                        char::from_digit(position as u32 + 1, 36).unwrap()
+                    })
+            })
+            .collect()
+    }
+
+    fn spellings<'a>(&self, word: &'a Word) -> Cow<'a, str> {
+        match &self.anagram_separator {
+            Some(separator) if !word.anagrams().is_empty() => {
+                let mut spellings = vec![word.original_word()];
+                spellings.extend(word.anagrams().iter().map(String::as_str));
+                Cow::Owned(spellings.join(separator))
+            }
+            _ => Cow::Borrowed(word.original_word()),
+        }
+    }
+}
+
+/// Writes the words of the sequence on a single line, separated by tabs, along
+/// with the extra columns. The number is only written if the columns ask for
+/// it.
+pub fn write_sequence<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    number: usize,
+    sequence: &[u32],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    write_line(out, columns, number, sequence, word_list, false)
+}
+
+/// Does the same as `write_sequence`, but escapes every backslash, tab,
+/// newline and carriage return in the words, which can come from the names of
+/// the lists or the anagram separator, as `\\`, `\t`, `\n` and `\r`. That
+/// way, every tab separates columns and every newline ends a combination, and
+/// the fields can be turned back into what they were.
+pub fn write_sequence_quoted<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    number: usize,
+    sequence: &[u32],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    write_line(out, columns, number, sequence, word_list, true)
+}
+
+fn write_line<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    number: usize,
+    sequence: &[u32],
+    word_list: &[Word],
+    quoted: bool,
+) -> std::io::Result<()> {
+    if columns.number {
+        write!(out, "{}\t", number)?;
+    }
+    if columns.word_count {
+        write!(out, "{}\t", sequence.len())?;
+    }
+    for (i, index) in sequence.iter().enumerate() {
+        if i > 0 {
+            write!(out, "\t")?;
+        }
+        let word = columns.word(&word_list[*index as usize]);
+        match quoted {
+            true => write!(out, "{}", escape_field(&word))?,
+            false => write!(out, "{}", word)?,
+        }
+    }
+    if columns.signature {
+        let mask = sequence
+            .iter()
+            .fold(0, |mask, i| mask | word_list[*i as usize].mask());
+        write!(out, "\t{}", letters_of(mask))?;
+    }
+    if columns.letter_map {
+        write!(out, "\t{}", Columns::letter_map(sequence, word_list))?;
+    }
+    if columns.positions {
+        write!(out, "\t{}", Columns::positions(sequence, word_list))?;
+    }
+    if let Some(difficulties) = &columns.difficulty {
+        write!(out, "\t{:.4}", get_difficulty(sequence, difficulties))?;
+    }
+    writeln!(out)
+}
+
+/// The spaces between the columns of `write_aligned`.
+const COLUMN_GAP: &str = "  ";
+
+/// Writes the sequences with the same columns as `write_sequence`, but with
+/// each column padded with spaces to its widest field, so that the columns
+/// line up in a terminal. The widths are only known once every sequence has
+/// been seen, so this holds all the lines in memory. Sequences with fewer
+/// words than the longest one get empty columns for the missing words, which
+/// keeps the columns after the words lined up as well.
+pub fn write_aligned<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    sequences: &[Vec<u32>],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    let width = sequences.iter().map(Vec::len).max().unwrap_or(0);
+    let rows: Vec<Vec<String>> = sequences
+        .iter()
+        .enumerate()
+        .map(|(i, sequence)| fields(columns, i + 1, sequence, word_list, width))
+        .collect();
+    let mut widths: Vec<usize> = vec![];
+    for row in &rows {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    for row in &rows {
+        let mut line = String::new();
+        for (i, (field, &width)) in row.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                line.push_str(COLUMN_GAP);
+            }
+            line.push_str(field);
+            line.extend(std::iter::repeat_n(' ', width - field.chars().count()));
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Returns the columns that `write_sequence` writes for the sequence, with
+/// empty words after its own up to `width` words.
+fn fields(
+    columns: &Columns,
+    number: usize,
+    sequence: &[u32],
+    word_list: &[Word],
+    width: usize,
+) -> Vec<String> {
+    let mut fields = vec![];
+    if columns.number {
+        fields.push(number.to_string());
+    }
+    if columns.word_count {
+        fields.push(sequence.len().to_string());
+    }
+    for &i in sequence {
+        fields.push(columns.word(&word_list[i as usize]).into_owned());
+    }
+    fields.resize(fields.len() + width - sequence.len(), String::new());
+    let mask = sequence
+        .iter()
+        .fold(0, |mask, i| mask | word_list[*i as usize].mask());
+    if columns.signature {
+        fields.push(letters_of(mask));
+    }
+    if columns.letter_map {
+        fields.push(Columns::letter_map(sequence, word_list));
+    }
+    if columns.positions {
+        fields.push(Columns::positions(sequence, word_list));
+    }
+    if let Some(difficulties) = &columns.difficulty {
+        fields.push(format!("{:.4}", get_difficulty(sequence, difficulties)));
+    }
+    fields
+}
+
+/// Escapes a field for `write_sequence_quoted`, which leaves it as it is if
+/// there is nothing to escape.
+fn escape_field(field: &str) -> Cow<'_, str> {
+    if !field.contains(['\\', '\t', '\n', '\r']) {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = String::with_capacity(field.len() + 2);
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// A line to write for each combination, with placeholders that are filled in
+/// for each of them, parsed from a string such as `{1} {2} (missing
+/// {unused})`.
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+enum Part {
+    Text(String),
+    /// The word at this position, starting at 1
+    Word(usize),
+    /// All the words, separated by spaces
+    Words,
+    Number,
+    Unused,
+    Letters,
+    LetterMap,
+    Positions,
+    Difficulty,
+}
+
+impl Template {
+    /// Parses the template for combinations of at most `max_words` words.
+    /// The placeholders are `{1}` up to `{max_words}` for the words, `{words}`
+    /// for all of them, `{number}`, `{unused}`, `{letters}`, `{letter_map}`,
+    /// `{positions}` and `{difficulty}`, and `{{` and `}}` are braces.
+    pub fn parse(template: &str, max_words: usize) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| {
+                        format!("the template has a {{ without a }}: {}", template)
+                    })?;
+                    let name = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    let part = match name {
+                        "words" => Part::Words,
+                        "number" => Part::Number,
+                        "unused" => Part::Unused,
+                        "letters" => Part::Letters,
+                        "letter_map" => Part::LetterMap,
+                        "positions" => Part::Positions,
+                        "difficulty" => Part::Difficulty,
+                        _ => match name.parse::<usize>() {
+                            Ok(position) if (1..=max_words).contains(&position) => {
+                                Part::Word(position)
+                            }
+                            Ok(position) => {
+                                return Err(format!(
+                                    "the template has {{{}}}, but the combinations have \
+                                     words 1 to {}",
+                                    position, max_words
+                                ))
+                            }
+                            Err(_) => {
+                                return Err(format!(
+                                    "unknown placeholder in the template: {{{}}}",
+                                    name
+                                ))
+                            }
+                        },
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(part);
+                }
+                '}' => return Err(format!("the template has a }} without a {{: {}", template)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Returns `true` if the template has `{difficulty}`.
+    pub fn needs_difficulty(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Difficulty))
+    }
+
+    /// Writes the template for the sequence, followed by a newline. The words
+    /// are written as they are for `write_sequence`, and the difficulty is
+    /// taken from the difficulties of the words. A position that is past the
+    /// end of a shorter sequence is left empty.
+    pub fn write<W: Write>(
+        &self,
+        out: &mut W,
+        columns: &Columns,
+        number: usize,
+        sequence: &[u32],
+        word_list: &[Word],
+        difficulties: Option<&[f64]>,
+    ) -> std::io::Result<()> {
+        let mask = sequence
+            .iter()
+            .fold(0, |mask, i| mask | word_list[*i as usize].mask());
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => write!(out, "{}", text)?,
+                Part::Word(position) => {
+                    if let Some(&i) = sequence.get(position - 1) {
+                        write!(out, "{}", columns.word(&word_list[i as usize]))?;
+                    }
+                }
+                Part::Words => {
+                    for (position, &i) in sequence.iter().enumerate() {
+                        if position > 0 {
+                            write!(out, " ")?;
+                        }
+                        write!(out, "{}", columns.word(&word_list[i as usize]))?;
+                    }
+                }
+                Part::Number => write!(out, "{}", number)?,
+                Part::Unused => write!(out, "{}", letters_of(!mask))?,
+                Part::Letters => write!(out, "{}", letters_of(mask))?,
+                Part::LetterMap => write!(out, "{}", Columns::letter_map(sequence, word_list))?,
+                Part::Positions => write!(out, "{}", Columns::positions(sequence, word_list))?,
+                Part::Difficulty => {
+                    let difficulties = difficulties.expect("the template needs difficulties");
+                    write!(out, "{:.4}", get_difficulty(sequence, difficulties))?;
+                }
+            }
+        }
+        writeln!(out)
+    }
+}
+
+/// Writes the sequence as a JSON object on a single line, with the words and
+/// the letters that they leave unused, along with the extra columns.
+pub fn write_json_line<W: Write>(
+    out: &mut W,
+    columns: &Columns,
+    number: usize,
+    sequence: &[u32],
+    word_list: &[Word],
+) -> std::io::Result<()> {
+    write!(out, "{{")?;
+    if columns.number {
+        write!(out, "\"number\":{},", number)?;
+    }
+    if columns.word_count {
+        write!(out, "\"word_count\":{},", sequence.len())?;
+    }
+    write!(out, "\"words\":[")?;
+    for (i, index) in sequence.iter().enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        write_json_string(out, &columns.word(&word_list[*index as usize]))?;
+    }
+    let mask = sequence
+        .iter()
+        .fold(0, |mask, i| mask | word_list[*i as usize].mask());
+    write!(out, "],\"unused\":\"{}\"", letters_of(!mask))?;
+    if columns.signature {
+        write!(out, ",\"letters\":\"{}\"", letters_of(mask))?;
+    }
+    if columns.letter_map {
+        write!(
+            out,
+            ",\"letter_map\":\"{}\"",
+            Columns::letter_map(sequence, word_list)
+        )?;
+    }
+    if columns.positions {
+        write!(
+            out,
+            ",\"positions\":\"{}\"",
+            Columns::positions(sequence, word_list)
+        )?;
+    }
+    if let Some(difficulties) = &columns.difficulty {
+        write!(
+            out,
+            ",\"difficulty\":{:.4}",
+            get_difficulty(sequence, difficulties)
+        )?;
+    }
+    writeln!(out, "}}")
+}
+
+/// Writes the text as a JSON string, with quotes around it.
+fn write_json_string<W: Write>(out: &mut W, text: &str) -> std::io::Result<()> {
+    write!(out, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(out, "\\\"")?,
+            '\\' => write!(out, "\\\\")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    write!(out, "\"")
+}
+
+/// A directory with a file for each letter, `a.txt` through `z.txt`. Each file
+/// is only created once something is written to it.
+pub struct LetterPartition {
+    dir: PathBuf,
+    files: Vec<Option<BufWriter<File>>>,
+}
+
+impl LetterPartition {
+    pub fn new(dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(LetterPartition {
+            dir: dir.to_path_buf(),
+            files: (0..26).map(|_| None).collect(),
+        })
+    }
+
+    /// Returns the file for the given lowercase letter, creating it if needed.
+    pub fn writer(&mut self, letter: u8) -> std::io::Result<&mut BufWriter<File>> {
+        let file = &mut self.files[(letter - b'a') as usize];
+        if file.is_none() {
+            let path = self.dir.join(format!("{}.txt", letter as char));
+            *file = Some(BufWriter::new(File::create(path)?));
+        }
+        Ok(file.as_mut().unwrap())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        for file in self.files.iter_mut().flatten() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use five_words::{get_words_from_lists, Options};
+
+    /// Undoes `escape_field`.
+    fn unescape_field(field: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = field.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('\\') => unescaped.push('\\'),
+                    Some('t') => unescaped.push('\t'),
+                    Some('n') => unescaped.push('\n'),
+                    Some('r') => unescaped.push('\r'),
+                    other => panic!("unexpected escape {:?} in {:?}", other, field),
+                },
+                c => unescaped.push(c),
+            }
+        }
+        unescaped
+    }
+
+    #[test]
+    fn escape_field_escapes_separators_and_backslashes() {
+        assert_eq!(escape_field("fjord"), "fjord");
+        assert_eq!(escape_field(""), "");
+        assert_eq!(escape_field("a\tb"), "a\\tb");
+        assert_eq!(escape_field("a\nb\r"), "a\\nb\\r");
+        assert_eq!(escape_field("a\\tb"), "a\\\\tb");
+        assert_eq!(escape_field("\"quoted\" 'too'"), "\"quoted\" 'too'");
+    }
+
+    #[test]
+    fn escape_field_borrows_when_there_is_nothing_to_escape() {
+        assert!(matches!(escape_field("nymph \"x\""), Cow::Borrowed(_)));
+        assert!(matches!(escape_field("a\tb"), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn escaped_fields_round_trip() {
+        for field in [
+            "",
+            "\t",
+            "\\",
+            "\\t",
+            "\n\r\n",
+            "\"a\ttab\"",
+            "trailing\\",
+            "mixed \\\t\"\n\r end",
+        ] {
+            let escaped = escape_field(field);
+            assert!(!escaped.contains(['\t', '\n', '\r']), "{:?}", escaped);
+            assert_eq!(unescape_field(&escaped), field);
+        }
+    }
+
+    #[test]
+    fn quoted_tsv_round_trips_adversarial_source_names() {
+        let lists = ["fjord\ngucks\n", "fjord\nnymph\n"];
+        let word_list = get_words_from_lists(
+            lists.iter().map(|list| list.as_bytes()),
+            &Options::default(),
+        )
+        .unwrap();
+        let names = vec![String::from("a\t\"list\"\\1"), String::from("")];
+        let columns = Columns {
+            number: true,
+            sources: Some(names),
+            ..Columns::default()
+        };
+        let sequence = [0, 1, 2];
+
+        let mut out = vec![];
+        write_sequence_quoted(&mut out, &columns, 7, &sequence, &word_list).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        let line = line.strip_suffix('\n').unwrap();
+        assert!(!line.contains('\n'));
+
+        let fields: Vec<String> = line.split('\t').map(unescape_field).collect();
+        let mut expected = vec![String::from("7")];
+        expected.extend(
+            sequence
+                .iter()
+                .map(|&i| columns.word(&word_list[i as usize]).into_owned()),
+        );
+        assert_eq!(fields, expected);
+        assert_eq!(fields[1], "fjord (a\t\"list\"\\1, )");
+    }
+
+    #[test]
+    fn quoted_tsv_round_trips_an_empty_anagram_separator() {
+        let word_list =
+            five_words::get_words("fjord\ndorfj\ngucks\n".as_bytes(), &Options::default()).unwrap();
+        let columns = Columns {
+            anagram_separator: Some(String::from("\n")),
+            ..Columns::default()
+        };
+        let mut out = vec![];
+        write_sequence_quoted(&mut out, &columns, 1, &[0, 1], &word_list).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "fjord\\ndorfj\tgucks\n");
+
+        let columns = Columns {
+            anagram_separator: Some(String::new()),
+            ..Columns::default()
+        };
+        let mut out = vec![];
+        write_sequence_quoted(&mut out, &columns, 1, &[0, 1], &word_list).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "fjorddorfj\tgucks\n");
+    }
+}
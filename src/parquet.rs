@@ -0,0 +1,189 @@
+//! Writing the combinations that were found to a Parquet file.
+
+use ::parquet::data_type::{ByteArray, ByteArrayType};
+use ::parquet::errors::ParquetError;
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::file::writer::SerializedFileWriter;
+use ::parquet::schema::parser::parse_message_type;
+use five_words::{letters_of, Word};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The number of combinations per row group.
+const ROW_GROUP_SIZE: usize = 100_000;
+
+/// Writes the combinations to a new Parquet file at the given path, with a
+/// row per combination, a string column per word, `word1` to `wordN`, and a
+/// column `unused_letter` with the letters that the combination leaves unused. If
+/// the combinations don't all have the same number of words, the word
+/// columns are optional, and the columns after a combination's last word are
+/// null.
+pub fn write_file(path: &Path, word_list: &[Word], sequences: &[Vec<u32>]) -> std::io::Result<()> {
+    write_file_inner(path, word_list, sequences).map_err(std::io::Error::other)
+}
+
+fn write_file_inner(
+    path: &Path,
+    word_list: &[Word],
+    sequences: &[Vec<u32>],
+) -> Result<(), ParquetError> {
+    let width = sequences.iter().map(Vec::len).max().unwrap_or(0);
+    let repetition = match sequences.iter().all(|sequence| sequence.len() == width) {
+        true => "REQUIRED",
+        false => "OPTIONAL",
+    };
+    let mut schema = String::from("message solutions {\n");
+    for i in 1..=width {
+        schema.push_str(&format!("{} BINARY word{} (UTF8);\n", repetition, i));
+    }
+    schema.push_str("REQUIRED BINARY unused_letter (UTF8);\n}");
+
+    let mut writer = SerializedFileWriter::new(
+        File::create(path)?,
+        Arc::new(parse_message_type(&schema)?),
+        Arc::new(WriterProperties::builder().build()),
+    )?;
+    for batch in sequences.chunks(ROW_GROUP_SIZE) {
+        let mut row_group = writer.next_row_group()?;
+        let mut column_index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            let (values, levels): (Vec<ByteArray>, Vec<i16>) = if column_index < width {
+                let mut values = vec![];
+                let mut levels = vec![];
+                for sequence in batch {
+                    match sequence.get(column_index) {
+                        Some(&i) => {
+                            values.push(ByteArray::from(word_list[i as usize].original_word()));
+                            levels.push(1);
+                        }
+                        None => levels.push(0),
+                    }
+                }
+                (values, levels)
+            } else {
+                let values = batch
+                    .iter()
+                    .map(|sequence| {
+                        let mask = sequence
+                            .iter()
+                            .fold(0, |mask, &i| mask | word_list[i as usize].mask());
+                        ByteArray::from(letters_of(!mask).as_str())
+                    })
+                    .collect();
+                (values, vec![])
+            };
+            // Only the optional columns have definition levels.
+            let levels = (!levels.is_empty() && repetition == "OPTIONAL").then_some(&levels[..]);
+            column
+                .typed::<ByteArrayType>()
+                .write_batch(&values, levels, None)?;
+            column.close()?;
+            column_index += 1;
+        }
+        row_group.close()?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::parquet::basic::Repetition;
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use ::parquet::record::Field;
+    use five_words::{get_words_from_lists, Options};
+
+    fn word_list() -> Vec<Word> {
+        let list = "fjord\ngucks\nnymph\nvibex\nwaltz\n";
+        get_words_from_lists([list.as_bytes()], &Options::default()).unwrap()
+    }
+
+    /// Writes the combinations and reads the file back, returning its column
+    /// names and repetitions and its rows as strings, with nulls as `None`.
+    #[allow(clippy::type_complexity)]
+    fn round_trip(
+        name: &str,
+        sequences: &[Vec<u32>],
+    ) -> (Vec<(String, Repetition)>, Vec<Vec<Option<String>>>) {
+        let path = std::env::temp_dir().join(format!(
+            "five-words-{}-{}.parquet",
+            name,
+            std::process::id()
+        ));
+        let word_list = word_list();
+        write_file(&path, &word_list, sequences).unwrap();
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), sequences.len() as i64);
+        let columns = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| {
+                let repetition = column.self_type().get_basic_info().repetition();
+                (column.name().to_string(), repetition)
+            })
+            .collect();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_, field)| match field {
+                        Field::Str(value) => Some(value.clone()),
+                        Field::Null => None,
+                        field => panic!("unexpected field {:?}", field),
+                    })
+                    .collect()
+            })
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        (columns, rows)
+    }
+
+    fn some(values: &[&str]) -> Vec<Option<String>> {
+        values.iter().map(|value| Some(value.to_string())).collect()
+    }
+
+    #[test]
+    fn combinations_of_one_length_have_required_columns() {
+        let (columns, rows) = round_trip("equal", &[vec![0, 1], vec![2, 3]]);
+        let expected: Vec<_> = ["word1", "word2", "unused_letter"]
+            .iter()
+            .map(|name| (name.to_string(), Repetition::REQUIRED))
+            .collect();
+        assert_eq!(columns, expected);
+        assert_eq!(
+            rows,
+            [
+                some(&["fjord", "gucks", "abehilmnpqtvwxyz"]),
+                some(&["nymph", "vibex", "acdfgjkloqrstuwz"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn combinations_of_mixed_lengths_have_optional_word_columns() {
+        let (columns, rows) = round_trip("mixed", &[vec![0, 1, 2], vec![4]]);
+        let expected = [
+            ("word1", Repetition::OPTIONAL),
+            ("word2", Repetition::OPTIONAL),
+            ("word3", Repetition::OPTIONAL),
+            ("unused_letter", Repetition::REQUIRED),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(name, repetition)| (name.to_string(), repetition))
+            .collect();
+        assert_eq!(columns, expected);
+        let mut last = vec![Some(String::from("waltz")), None, None];
+        last.push(Some(String::from("bcdefghijkmnopqrsuvxy")));
+        assert_eq!(
+            rows,
+            [some(&["fjord", "gucks", "nymph", "abeilqtvwxz"]), last]
+        );
+    }
+}
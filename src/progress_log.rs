@@ -0,0 +1,130 @@
+//! Writing down the progress of the search as JSON lines at regular
+//! intervals, for other programs to keep track of a long search.
+
+use five_words::ProgressSink;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// A `ProgressSink` that passes every update on, and that keeps the numbers
+/// for a thread that appends them to the log file every interval, and once
+/// more when it's dropped.
+pub struct ProgressLog<'a> {
+    progress: &'a dyn ProgressSink,
+    counters: Arc<Counters>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// The numbers that the search updates and that the thread reads.
+#[derive(Default)]
+struct Counters {
+    total: AtomicU64,
+    done: AtomicU64,
+    found: AtomicU64,
+    pairs: AtomicBool,
+}
+
+impl<'a> ProgressLog<'a> {
+    /// Opens the log file to append to, and starts the thread that writes to
+    /// it.
+    pub fn new(
+        path: &Path,
+        interval: Duration,
+        progress: &'a dyn ProgressSink,
+    ) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let counters = Arc::new(Counters::default());
+        let (stop, stopped) = mpsc::channel::<()>();
+        let started = Instant::now();
+        let thread = thread::spawn({
+            let counters = Arc::clone(&counters);
+            move || loop {
+                // The sender is only ever dropped, so anything but a timeout
+                // means that the search is done.
+                let last = !matches!(
+                    stopped.recv_timeout(interval),
+                    Err(RecvTimeoutError::Timeout)
+                );
+                if let Err(e) = write_snapshot(&mut file, &counters, started.elapsed()) {
+                    warn!(error = %e, "couldn't write to the progress log");
+                    return;
+                }
+                if last {
+                    return;
+                }
+            }
+        });
+        Ok(ProgressLog {
+            progress,
+            counters,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Appends a line such as
+/// `{"elapsed":12.345,"done":120,"total":541,"unit":"words","found":2279}` to
+/// the file. The unit is what the tasks that are done are, which is either
+/// the words that the search starts from or pairs of them.
+fn write_snapshot(file: &mut File, counters: &Counters, elapsed: Duration) -> std::io::Result<()> {
+    writeln!(
+        file,
+        "{{\"elapsed\":{:.3},\"done\":{},\"total\":{},\"unit\":\"{}\",\"found\":{}}}",
+        elapsed.as_secs_f64(),
+        counters.done.load(Ordering::Relaxed),
+        counters.total.load(Ordering::Relaxed),
+        match counters.pairs.load(Ordering::Relaxed) {
+            true => "pairs",
+            false => "words",
+        },
+        counters.found.load(Ordering::Relaxed)
+    )
+}
+
+impl Drop for ProgressLog<'_> {
+    /// Stops the thread, after it wrote the last numbers.
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl ProgressSink for ProgressLog<'_> {
+    /// Also starts counting the tasks that are done and the combinations that
+    /// are found from 0 again, since this is another search.
+    fn set_total(&self, n: u64) {
+        self.counters.total.store(n, Ordering::Relaxed);
+        self.counters.done.store(0, Ordering::Relaxed);
+        self.counters.found.store(0, Ordering::Relaxed);
+        self.counters.pairs.store(false, Ordering::Relaxed);
+        self.progress.set_total(n);
+    }
+
+    fn tasks_are_pairs(&self) {
+        self.counters.pairs.store(true, Ordering::Relaxed);
+        self.progress.tasks_are_pairs();
+    }
+
+    fn advance(&self, n: u64) {
+        self.counters.done.fetch_add(n, Ordering::Relaxed);
+        self.progress.advance(n);
+    }
+
+    fn set_found(&self, n: u64) {
+        // The threads of the search report in any order.
+        self.counters.found.fetch_max(n, Ordering::Relaxed);
+        self.progress.set_found(n);
+    }
+
+    forward_progress!(progress; set_current, finish_start, finish_task, is_cancelled);
+}
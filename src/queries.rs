@@ -0,0 +1,105 @@
+//! The batch mode, which reads a file of constraint sets and runs a search for
+//! each of them against the same word list, without reading the word lists or
+//! precomputing which words are disjoint again.
+
+use crate::output::{write_sequence, Columns};
+use crate::{
+    find_word, invalid_input, order_sequences, parse_letters, progress_bar, Args, IndicatifProgress,
+};
+use five_words::{Constraints, Options, Search, Word};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// A constraint set from the file, with the name that its results are listed
+/// under.
+struct Query {
+    label: String,
+    constraints: Constraints,
+}
+
+/// Parses a line such as `easy: require fjord nymph; exclude qz` into a
+/// query. The label before the colon is optional, and is the line number
+/// without one. Each part between semicolons is a `require` or an `exclude`,
+/// as in the interactive mode.
+fn parse_query(line: &str, number: usize, word_list: &[Word]) -> Result<Query, String> {
+    let (label, rest) = match line.split_once(':') {
+        Some((label, rest)) => (label.trim().to_string(), rest),
+        None => (format!("query {}", number), line),
+    };
+    let mut constraints = Constraints::default();
+    for part in rest.split(';') {
+        let mut words = part.split_whitespace();
+        match words.next() {
+            Some("require") => {
+                for word in words {
+                    constraints.required.push(find_word(word_list, word)?);
+                }
+            }
+            Some("exclude") => {
+                constraints.excluded_letters |= parse_letters(&words.collect::<String>())?;
+            }
+            Some(name) => {
+                return Err(format!(
+                    "unknown constraint: {}, only require and exclude are",
+                    name
+                ))
+            }
+            None => {}
+        }
+    }
+    Ok(Query { label, constraints })
+}
+
+/// Reads every query from the file, skipping empty lines and the lines that
+/// start with `#`, and stops at the first one that isn't valid.
+fn read_queries(path: &Path, word_list: &[Word]) -> std::io::Result<Vec<Query>> {
+    let mut queries = vec![];
+    for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let query = parse_query(&line, number + 1, word_list).map_err(|message| {
+            invalid_input(format!(
+                "line {} of {}: {}",
+                number + 1,
+                path.display(),
+                message
+            ))
+        })?;
+        queries.push(query);
+    }
+    Ok(queries)
+}
+
+/// Runs every query in the file, and writes the combinations of each one
+/// after a line with its label and the number of combinations, with an empty
+/// line between the blocks.
+pub fn run<W: Write>(
+    out: &mut W,
+    path: &Path,
+    args: &Args,
+    word_list: &Vec<Word>,
+    options: &Options,
+    columns: &Columns,
+    difficulties: Option<&[f64]>,
+) -> std::io::Result<()> {
+    let queries = read_queries(path, word_list)?;
+    let search = Search::new(word_list, args.words as usize, options);
+    for (i, query) in queries.iter().enumerate() {
+        let progress = IndicatifProgress(progress_bar(args));
+        let mut sequences = search.find(&query.constraints, &progress);
+        progress.0.finish_and_clear();
+        order_sequences(args, &mut sequences, word_list, difficulties);
+
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "# {}: {} combinations", query.label, sequences.len())?;
+        for (i, sequence) in sequences.iter().enumerate() {
+            write_sequence(out, columns, i + 1, sequence, word_list)?;
+        }
+    }
+    out.flush()
+}
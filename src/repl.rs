@@ -0,0 +1,141 @@
+//! The interactive mode, which reads commands from standard input and runs a
+//! search for every `find`, without reading the word lists or precomputing
+//! which words are disjoint again.
+
+use crate::output::{write_sequence, Columns};
+use crate::{find_word, order_sequences, progress_bar, Args, IndicatifProgress};
+use five_words::{letters_of, Constraints, Options, Search, Word};
+use std::io::{BufRead, IsTerminal, Write};
+
+const HELP: &str = "\
+Commands:
+  require WORD...   Only find combinations with these words
+  exclude LETTERS   Only find combinations without these letters
+  clear             Forget all required words and excluded letters
+  show              Show the required words and excluded letters
+  find              Find the combinations and write them to standard output
+  help              Show this list of commands
+  quit              Stop";
+
+enum Command {
+    Require(Vec<String>),
+    Exclude(String),
+    Clear,
+    Show,
+    Find,
+    Help,
+    Quit,
+}
+
+/// Parses a line of input into a command, or returns `None` for an empty line.
+fn parse_command(line: &str) -> Result<Option<Command>, String> {
+    let mut words = line.split_whitespace();
+    let name = match words.next() {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let arguments: Vec<String> = words.map(String::from).collect();
+    let command = match (name, arguments.is_empty()) {
+        ("require", false) => Command::Require(arguments),
+        ("exclude", false) => Command::Exclude(arguments.concat()),
+        ("require" | "exclude", true) => {
+            return Err(format!("{} needs at least one argument", name));
+        }
+        ("clear", true) => Command::Clear,
+        ("show", true) => Command::Show,
+        ("find", true) => Command::Find,
+        ("help", true) => Command::Help,
+        ("quit" | "exit", true) => Command::Quit,
+        ("clear" | "show" | "find" | "help" | "quit" | "exit", false) => {
+            return Err(format!("{} doesn't take any arguments", name));
+        }
+        _ => return Err(format!("unknown command: {}, try help", name)),
+    };
+    Ok(Some(command))
+}
+
+/// Reads commands from standard input until it ends or until `quit`.
+pub fn run(
+    args: &Args,
+    word_list: &Vec<Word>,
+    options: &Options,
+    columns: &Columns,
+    difficulties: Option<&[f64]>,
+) -> std::io::Result<()> {
+    let search = Search::new(word_list, args.words as usize, options);
+    let mut constraints = Constraints::default();
+    let stdin = std::io::stdin();
+    let prompt = stdin.is_terminal();
+    if prompt {
+        eprintln!(
+            "Read {} words. Type help for a list of commands.",
+            word_list.len()
+        );
+    }
+
+    let mut lines = stdin.lock().lines();
+    loop {
+        if prompt {
+            eprint!("> ");
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let command = match parse_command(&line) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(message) => {
+                eprintln!("Error: {}", message);
+                continue;
+            }
+        };
+
+        match command {
+            Command::Require(words) => {
+                let required: Result<Vec<u32>, String> =
+                    words.iter().map(|w| find_word(word_list, w)).collect();
+                match required {
+                    Ok(required) => constraints.required.extend(required),
+                    Err(message) => eprintln!("Error: {}", message),
+                }
+            }
+            Command::Exclude(letters) => match letters.bytes().find(|c| !c.is_ascii_lowercase()) {
+                Some(c) => eprintln!("Error: {} is not a lowercase letter", c as char),
+                None => {
+                    constraints.excluded_letters |=
+                        letters.bytes().fold(0, |mask, c| mask | 1 << (c - b'a'));
+                }
+            },
+            Command::Clear => constraints = Constraints::default(),
+            Command::Show => {
+                let required: Vec<&str> = constraints
+                    .required
+                    .iter()
+                    .map(|&i| word_list[i as usize].original_word())
+                    .collect();
+                eprintln!("Required words: {}", required.join(" "));
+                eprintln!(
+                    "Excluded letters: {}",
+                    letters_of(constraints.excluded_letters)
+                );
+            }
+            Command::Find => {
+                let progress = IndicatifProgress(progress_bar(args));
+                let mut sequences = search.find(&constraints, &progress);
+                progress.0.finish_and_clear();
+                order_sequences(args, &mut sequences, word_list, difficulties);
+
+                let mut out = std::io::stdout().lock();
+                for (i, sequence) in sequences.iter().enumerate() {
+                    write_sequence(&mut out, columns, i + 1, sequence, word_list)?;
+                }
+                out.flush()?;
+                eprintln!("Found {} combinations.", sequences.len());
+            }
+            Command::Help => eprintln!("{}", HELP),
+            Command::Quit => break,
+        }
+    }
+    Ok(())
+}
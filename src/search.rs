@@ -0,0 +1,1420 @@
+//! The exhaustive search for combinations of disjoint words.
+
+use crate::cursor::SolutionCursor;
+use crate::word::dense_letters;
+use crate::{get_words, NoProgress, Options, ProgressSink, Solutions, Strategy, Word};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use tracing::{debug_span, info};
+
+/// Returns all combinations of `sequence_length` words in the list that don't
+/// have any characters in common, as indices into the list.
+pub fn get_disjoint_indices(
+    word_list: &Vec<Word>,
+    sequence_length: usize,
+    options: &Options,
+    progress: &dyn ProgressSink,
+) -> Vec<Vec<u32>> {
+    Search::new(word_list, sequence_length, options).find(&Constraints::default(), progress)
+}
+
+/// Does the same as `get_disjoint_indices`, but with the default options, on a
+/// word list that is already in memory, and returns the words themselves
+/// rather than their indices. Like in a word list that is read from a file,
+/// only the words of five different lowercase letters are used, and only the
+/// first of each set of anagrams.
+///
+/// ```
+/// let words = ["fjord", "gucks", "nymph", "vibex", "waltz", "hello", "jowls"];
+/// let solutions = five_words::solve_from_strings(&words, 5);
+/// assert_eq!(solutions, vec![vec!["fjord", "gucks", "nymph", "vibex", "waltz"]]);
+/// ```
+pub fn solve_from_strings(words: &[&str], sequence_length: usize) -> Vec<Vec<String>> {
+    let options = Options::default();
+    let input = words.join("\n");
+    let word_list = get_words(input.as_bytes(), &options).expect("a string is always valid UTF-8");
+    let sequences = get_disjoint_indices(&word_list, sequence_length, &options, &NoProgress);
+    Solutions::new(&word_list, sequences)
+        .into_iter()
+        .map(|words| words.into_iter().map(String::from).collect())
+        .collect()
+}
+
+/// Removes the words that can't be in a combination with any other word,
+/// because every other word has a letter in common with them, or more letters
+/// than the letter budget allows. Returns the number of words that were
+/// removed. This doesn't change the combinations of two or more words, but
+/// the search no longer has to start from those words.
+pub fn remove_words_without_partners(word_list: &mut Vec<Word>, options: &Options) -> usize {
+    let budget = options.letter_budget.unwrap_or(0);
+    let keep: Vec<bool> = word_list
+        .par_iter()
+        .enumerate()
+        .map(|(i, word)| {
+            word_list
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != i && (word.bits & other.bits).count_ones() <= budget)
+        })
+        .collect();
+    let before = word_list.len();
+    let mut keep = keep.into_iter();
+    word_list.retain(|_| keep.next().unwrap());
+    before - word_list.len()
+}
+
+/// Restrictions on the combinations that `Search::find` looks for, on top of
+/// the ones in the `Options`.
+#[derive(Clone, Default)]
+pub struct Constraints {
+    /// The indices of the words that every combination has to contain
+    pub required: Vec<u32>,
+
+    /// The letters that no word in a combination may contain, with bit 0 for
+    /// the letter a
+    pub excluded_letters: u32,
+}
+
+impl Constraints {
+    fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.excluded_letters == 0
+    }
+}
+
+/// Does the same as `get_disjoint_indices`, but seeds the search with all
+/// disjoint pairs instead of with single words.
+fn get_disjoint_indices_from_pairs(search: &Search, progress: &dyn ProgressSink) -> Vec<Vec<u32>> {
+    let word_list_len = search.word_list.len() as u32;
+    let found = AtomicU64::new(0);
+
+    // For each word, the words after it in the list that it is disjoint with.
+    // Together they make up all the disjoint pairs.
+    let partners: Vec<Vec<u32>> = (0..word_list_len)
+        .into_par_iter()
+        .map(|i| {
+            (i + 1..word_list_len)
+                .filter(|&j| search.may_choose(j))
+                .filter(|&j| search.word(i).is_disjoint_with(search.word(j)))
+                .collect()
+        })
+        .collect();
+    let pairs: Vec<(u32, u32)> = (0..word_list_len)
+        .zip(&partners)
+        .filter(|&(i, _)| search.may_start(i))
+        .flat_map(|(i, partners)| partners.iter().map(move |&j| (i, j)))
+        .filter(|&(i, j)| search.may_follow(&[i], 0, j))
+        .collect();
+    // The words that the pairs start from are states of the search as well,
+    // and the ones that no pair starts from are dead ends, the same as when
+    // the search starts from single words.
+    let starts = search.starts();
+    let paired: HashSet<u32> = pairs.iter().map(|&(i, _)| i).collect();
+    search.add_to_tree(TreeSize {
+        nodes: starts.len() as u64,
+        leaves: starts.iter().filter(|i| !paired.contains(i)).count() as u64,
+    });
+    progress.set_total(pairs.len() as u64);
+    progress.tasks_are_pairs();
+
+    let result = pairs.into_par_iter().map(|(i, j)| {
+        if progress.is_cancelled() {
+            progress.advance(1);
+            return vec![];
+        }
+        let started = Instant::now();
+        let names = search.names(&[i, j]);
+        let _span = debug_span!("start", words = %names).entered();
+        progress.set_current(&names);
+        // Every word that can follow the pair is a partner of its first word,
+        // so those are the only ones worth passing on.
+        let partial = search.get_disjoint_indices_parallel(vec![i, j], 0, &partners[i as usize]);
+        progress.finish_task(i, started.elapsed(), partial.len());
+
+        let len = partial.len() as u64;
+        progress.set_found(found.fetch_add(len, Ordering::Relaxed) + len);
+        progress.advance(1);
+        partial
+    });
+
+    result.flatten().collect()
+}
+
+/// The word list and settings that stay the same throughout a search. A
+/// `Search` can be reused to find combinations under different constraints,
+/// without precomputing which words are disjoint again.
+pub struct Search<'a> {
+    pub(crate) word_list: &'a Vec<Word>,
+    pub(crate) sequence_length: usize,
+    pub(crate) options: &'a Options,
+
+    /// For each word, the indices of all the words that it is disjoint with
+    /// and that may be chosen, in order. This is only computed if it fits in
+    /// the memory budget.
+    adjacency: Option<Vec<Vec<u32>>>,
+
+    /// For each word, whether the search may choose it, which is only not the
+    /// case for words outside of the allowed words.
+    choosable: Vec<bool>,
+
+    /// The number of letters in the longest word in the list
+    pub(crate) max_word_length: u32,
+
+    /// The number of states that the searches so far reached, and how many of
+    /// those had no words added to them, as described for `TreeSize`
+    nodes: AtomicU64,
+    leaves: AtomicU64,
+}
+
+/// The size of the tree of states that the searches went through: a node for
+/// every state the search reached, from the words it started from to the
+/// combinations, and the leaves, which are the nodes that no word was added
+/// to, because they are combinations or because no word could follow them.
+/// Unlike the time a search takes, this is the same on every machine.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct TreeSize {
+    /// The number of states, with the pairs strategy starting from the pairs
+    pub nodes: u64,
+
+    /// The number of those states that no word was added to
+    pub leaves: u64,
+}
+
+/// The number of words whose partners are counted to estimate the size of the
+/// adjacency lists.
+const ADJACENCY_SAMPLE_SIZE: usize = 64;
+
+impl<'a> Search<'a> {
+    pub fn new(word_list: &'a Vec<Word>, sequence_length: usize, options: &'a Options) -> Self {
+        let len =
+            u32::try_from(word_list.len()).expect("the word list has more words than fit a u32");
+        let estimate = estimate_adjacency_size(word_list);
+        let fits = options.max_memory.is_none_or(|budget| estimate <= budget);
+        let megabytes = format_args!("{:.1}", estimate as f64 / 1_000_000.0);
+        // The adjacency lists only hold the disjoint words, which is not what
+        // the search is looking for with a letter budget.
+        let precompute = fits && options.letter_budget.is_none();
+        if options.letter_budget.is_some() {
+            info!("not precomputing which words are disjoint, because of the letter budget");
+        } else if fits {
+            info!(megabytes, "precomputing which words are disjoint");
+        } else {
+            info!(
+                megabytes,
+                "not precomputing which words are disjoint, which is over the memory budget"
+            );
+        }
+
+        let choosable: Vec<bool> = word_list
+            .iter()
+            .map(|word| {
+                options
+                    .allowed_words
+                    .as_ref()
+                    .is_none_or(|allowed| allowed.contains(word.original_word()))
+            })
+            .collect();
+
+        let adjacency = precompute.then(|| {
+            (0..len)
+                .into_par_iter()
+                .map(|i| {
+                    (0..len)
+                        .filter(|&j| choosable[j as usize])
+                        .filter(|&j| word_list[i as usize].is_disjoint_with(&word_list[j as usize]))
+                        .collect()
+                })
+                .collect()
+        });
+
+        Search {
+            word_list,
+            sequence_length,
+            options,
+            adjacency,
+            choosable,
+            max_word_length: word_list
+                .iter()
+                .map(|w| w.bits.count_ones())
+                .max()
+                .unwrap_or(0),
+            nodes: AtomicU64::new(0),
+            leaves: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the size of the tree of all the searches with this `Search` so
+    /// far, across all threads.
+    pub fn tree_size(&self) -> TreeSize {
+        TreeSize {
+            nodes: self.nodes.load(Ordering::Relaxed),
+            leaves: self.leaves.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Adds to the size of the tree, which each task only does once it's
+    /// done, to keep the threads from contending for the counters.
+    fn add_to_tree(&self, tree: TreeSize) {
+        self.nodes.fetch_add(tree.nodes, Ordering::Relaxed);
+        self.leaves.fetch_add(tree.leaves, Ordering::Relaxed);
+    }
+
+    /// Returns the number of words in the list.
+    fn len(&self) -> u32 {
+        self.word_list.len() as u32
+    }
+
+    /// Returns the number of letters that the words may have, which is the
+    /// ones that aren't excluded.
+    pub(crate) fn alphabet(&self) -> u32 {
+        26 - self.options.excluded_letters.count_ones()
+    }
+
+    /// Returns the word at index `i`.
+    pub(crate) fn word(&self, i: u32) -> &'a Word {
+        &self.word_list[i as usize]
+    }
+
+    /// Returns, for each word, the number of words that may be chosen that it
+    /// is disjoint with. The fewer partners a word has, the more of the other
+    /// words choosing it rules out. This uses the adjacency lists if they were
+    /// precomputed.
+    pub fn partner_counts(&self) -> Vec<u32> {
+        match &self.adjacency {
+            Some(adjacency) => adjacency.iter().map(|a| a.len() as u32).collect(),
+            None => (0..self.len())
+                .into_par_iter()
+                .map(|i| {
+                    (0..self.len())
+                        .filter(|&j| self.may_choose(j))
+                        .filter(|&j| self.word(i).is_disjoint_with(self.word(j)))
+                        .count() as u32
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the indices of the words that may be chosen that the word at
+    /// index `i` is disjoint with, in order. This uses the adjacency lists if
+    /// they were precomputed.
+    pub fn partners(&self, i: u32) -> Vec<u32> {
+        match &self.adjacency {
+            Some(adjacency) => adjacency[i as usize].clone(),
+            None => (0..self.len())
+                .filter(|&j| self.may_choose(j))
+                .filter(|&j| self.word(i).is_disjoint_with(self.word(j)))
+                .collect(),
+        }
+    }
+
+    /// Changes the number of words in a combination for the searches after
+    /// this, which can still use the words that are disjoint with each word
+    /// that `new` worked out.
+    pub fn set_sequence_length(&mut self, sequence_length: usize) {
+        self.sequence_length = sequence_length;
+    }
+
+    /// Returns all combinations of `sequence_length` words that don't have any
+    /// characters in common and that satisfy the constraints, as indices into
+    /// the list. The required words come first in each combination, in the
+    /// order they were given in.
+    pub fn find(&self, constraints: &Constraints, progress: &dyn ProgressSink) -> Vec<Vec<u32>> {
+        // The pairs only hold the words after the first word of each pair,
+        // which doesn't work if the slots allow the words to come in any order,
+        // and a pair may already cover more letters than are asked for, or
+        // leave out a word that is a maximal set on its own, and several pairs
+        // start with the same word, so they can't tell which combination that
+        // starts with it comes first. The pairs are
+        // also only the disjoint ones, regardless of a letter budget.
+        if self.options.strategy == Strategy::Pairs
+            && self.sequence_length >= 2
+            && self.options.slot_sources.is_none()
+            && self.options.slot_constraints.is_none()
+            && self.options.letters_covered.is_none()
+            && !self.options.maximal
+            && !self.options.one_per_start
+            && self.options.letter_budget.is_none()
+            && constraints.is_empty()
+        {
+            return get_disjoint_indices_from_pairs(self, progress);
+        }
+
+        let required = &constraints.required;
+        let excluded = dense_letters(constraints.excluded_letters, self.options.excluded_letters);
+        let budget = self.options.letter_budget.unwrap_or(0);
+        let mut covered = 0;
+        for (slot, &i) in required.iter().enumerate() {
+            let mask = self.word(i).bits;
+            let overlap =
+                self.overlap_of(&required[..slot], covered) + (mask & covered).count_ones();
+            if mask & excluded != 0
+                || overlap > budget
+                || !self.may_follow(&required[..slot], slot, i)
+            {
+                return vec![];
+            }
+            covered |= mask;
+        }
+        match self.progress_of(required.len(), covered) {
+            Goal::Reached => return vec![required.clone()],
+            Goal::Passed => return vec![],
+            Goal::Ahead => {}
+        }
+
+        // Without constraints every word can start a combination, and the
+        // adjacency lists give its candidates. Otherwise, the candidates are
+        // the words that are still allowed, the same for every start.
+        let left = budget - self.overlap_of(required, covered);
+        let candidates: Option<Vec<u32>> = (!constraints.is_empty()).then(|| {
+            (0..self.len())
+                .filter(|&i| {
+                    let mask = self.word(i).bits;
+                    self.may_choose(i)
+                        && mask & excluded == 0
+                        && (mask & covered).count_ones() <= left
+                })
+                .collect()
+        });
+        let starts: Vec<u32> = match &candidates {
+            Some(candidates) => candidates
+                .iter()
+                .copied()
+                .filter(|&i| self.may_start(i) && self.may_follow(required, required.len(), i))
+                .collect(),
+            None => self.starts(),
+        };
+        let found = AtomicU64::new(0);
+        progress.set_total(starts.len() as u64);
+
+        let result = starts.into_par_iter().map(|i| {
+            let _span = debug_span!("start", word = %self.word(i).original_word).entered();
+            if progress.is_cancelled() {
+                progress.advance(1);
+                return vec![];
+            }
+            let started = Instant::now();
+            progress.set_current(&self.word(i).original_word);
+            let mut state = required.clone();
+            state.push(i);
+            let partial = match &candidates {
+                Some(candidates) => {
+                    self.get_disjoint_indices_parallel(state, required.len(), candidates)
+                }
+                None => self.get_disjoint_indices_parallel(state, 0, &self.first_candidates(i)),
+            };
+
+            // Reporting once per starting word rather than once per
+            // combination keeps the progress sink off the hot path.
+            progress.finish_task(i, started.elapsed(), partial.len());
+            progress.finish_start(i, &partial);
+            let len = partial.len() as u64;
+            progress.set_found(found.fetch_add(len, Ordering::Relaxed) + len);
+            progress.advance(1);
+            partial
+        });
+
+        result.flatten().collect()
+    }
+
+    /// Returns a cursor over the combinations that `find` would return without
+    /// constraints, which computes them a batch at a time.
+    pub fn cursor(&self) -> SolutionCursor<'_, 'a> {
+        SolutionCursor::new(self)
+    }
+
+    /// Returns the indices of the words that may follow the word at index
+    /// `i`, if it's the first word of a combination. With the adjacency lists
+    /// these are exactly the words it is disjoint with, and otherwise it's all
+    /// the words that may be chosen, which `get_disjoint_indices_partial` then
+    /// prunes.
+    pub(crate) fn first_candidates(&self, i: u32) -> Vec<u32> {
+        match &self.adjacency {
+            Some(adjacency) => adjacency[i as usize].clone(),
+            None => (0..self.len()).filter(|&j| self.may_choose(j)).collect(),
+        }
+    }
+
+    /// Returns `true` if the search may choose the word at index `i` for a
+    /// combination.
+    pub(crate) fn may_choose(&self, i: u32) -> bool {
+        self.choosable[i as usize]
+    }
+
+    /// Returns the words at the indices, separated by spaces.
+    pub(crate) fn names(&self, indices: &[u32]) -> String {
+        let names: Vec<&str> = indices
+            .iter()
+            .map(|&i| self.word(i).original_word())
+            .collect();
+        names.join(" ")
+    }
+
+    /// Returns `true` if the combinations that start with the word at index
+    /// `i` are searched, which is the case if the word may be chosen, if it's
+    /// in the shard that is searched, and if it's among the words that the
+    /// search starts from and not among the ones it skips.
+    pub(crate) fn may_start(&self, i: u32) -> bool {
+        self.may_choose(i)
+            && self
+                .options
+                .shard
+                .is_none_or(|shard| shard.contains(i as usize))
+            && self
+                .options
+                .starts_before
+                .is_none_or(|end| (i as usize) < end)
+            && !self.options.skip_starts.contains(&i)
+    }
+
+    /// Returns the indices of the words that the combinations that are
+    /// searched may start with, without required words. The words that the
+    /// first slot doesn't allow are left out, rather than skipped once the
+    /// search gets to them, so that the progress is measured against the
+    /// words that are actually searched from.
+    pub(crate) fn starts(&self) -> Vec<u32> {
+        (0..self.len())
+            .filter(|&i| self.may_start(i) && self.may_follow(&[], 0, i))
+            .collect()
+    }
+
+    /// Returns the indices in `valid_indices` of the words that are disjoint
+    /// with the word at `index`, in the same order.
+    pub(crate) fn prune(&self, index: u32, valid_indices: &[u32]) -> Vec<u32> {
+        valid_indices
+            .iter()
+            .filter(|&&i| self.word(index).is_disjoint_with(self.word(i)))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the candidates in `valid_indices` that may still be added to the
+    /// state, which covers the letters in `mask`, in the same order. Without
+    /// a letter budget, the candidates are already disjoint with the words
+    /// before the last one, so this only has to check that one. With a budget,
+    /// it keeps the words that overlap with the state by at most what is left
+    /// of it.
+    fn next_candidates(&self, state: &[u32], mask: u32, valid_indices: &[u32]) -> Vec<u32> {
+        let budget = match self.options.letter_budget {
+            Some(budget) => budget,
+            None => {
+                let last_index = *state.last().expect("state must not be empty");
+                return self.prune(last_index, valid_indices);
+            }
+        };
+        let left = budget - self.overlap_of(state, mask);
+        valid_indices
+            .iter()
+            .copied()
+            .filter(|&i| (self.word(i).bits & mask).count_ones() <= left)
+            .collect()
+    }
+
+    /// Panics if the candidates that `next_candidates` returned for the state,
+    /// which covers the letters in `mask`, aren't what they should be: every
+    /// one of them has to be disjoint with the state, or within the letter
+    /// budget, and they have to be some of the previous candidates in the same
+    /// order, so that no word the search has already gone past comes back.
+    /// Without slots, the words after the fixed ones also have to be in the
+    /// order of the word list.
+    fn check_candidates(
+        &self,
+        state: &[u32],
+        fixed: usize,
+        mask: u32,
+        previous: &[u32],
+        candidates: &[u32],
+    ) {
+        let left = self
+            .options
+            .letter_budget
+            .map_or(0, |budget| budget - self.overlap_of(state, mask));
+        let mut rest = previous.iter();
+        for &i in candidates {
+            let overlap = (self.word(i).bits & mask).count_ones();
+            assert!(
+                overlap <= left,
+                "{} is a candidate after {}, but has {} letters in common with them",
+                self.word(i),
+                self.names(state),
+                overlap
+            );
+            assert!(
+                rest.any(|&j| j == i),
+                "{} is a candidate after {}, but wasn't one of the candidates before, \
+                 or not in the same order",
+                self.word(i),
+                self.names(state)
+            );
+        }
+        if self.options.slot_sources.is_none() && self.options.slot_constraints.is_none() {
+            let free = &state[fixed.min(state.len())..];
+            assert!(
+                free.windows(2).all(|pair| pair[0] < pair[1]),
+                "the words of {} after the first {} aren't in the order of the word list",
+                self.names(state),
+                fixed
+            );
+        }
+    }
+
+    /// Returns how many letters of the words in the state are letters that
+    /// another word in it has as well, given the letters it covers.
+    fn overlap_of(&self, state: &[u32], mask: u32) -> u32 {
+        let letters: u32 = state.iter().map(|&i| self.word(i).bits.count_ones()).sum();
+        letters - mask.count_ones()
+    }
+
+    /// Returns `true` if the word at index `next` may be added to the state,
+    /// assuming that it is disjoint with the words in it. The first `fixed`
+    /// words of the state were chosen in advance, and the order only matters
+    /// among the words after them. Without slots, those words have to be in the
+    /// same order as in the word list, which makes sure every combination is
+    /// only found once.
+    ///
+    /// With slots, `next` has to come from one of the lists of its slot, and
+    /// meet the constraints of its slot. It only has to come after the word in
+    /// the last slot that allows the same lists and has the same constraints,
+    /// because otherwise the same combination would be found once for every
+    /// order of the words in those slots.
+    ///
+    /// Either way, `next` may not form a banned pair with any of the words in
+    /// the state, with `same_pattern`, it has to have the same pattern of
+    /// vowels and consonants as the first word of the state, and with
+    /// `distinct_first_letters`, it may not start with the same letter as any
+    /// of them.
+    pub(crate) fn may_follow(&self, state: &[u32], fixed: usize, next: u32) -> bool {
+        self.is_in_order(state, fixed, next)
+            && !self.is_banned_with(state, next)
+            && self.has_pattern_of(state, next)
+            && self.has_first_letter_apart_from(state, next)
+    }
+
+    /// Checks the order and the slots, as described for `may_follow`.
+    fn is_in_order(&self, state: &[u32], fixed: usize, next: u32) -> bool {
+        let (sources, constraints) = (&self.options.slot_sources, &self.options.slot_constraints);
+        if sources.is_none() && constraints.is_none() {
+            return state.len() == fixed || next > state[state.len() - 1];
+        }
+        let slot = state.len();
+        let word = self.word(next);
+        if sources
+            .as_ref()
+            .is_some_and(|slots| word.sources & slots[slot] == 0)
+            || constraints
+                .as_ref()
+                .is_some_and(|slots| !slots[slot].allows(word))
+        {
+            return false;
+        }
+        // Slots that allow the same words can have them in any order.
+        let same_slot = |i: usize| {
+            sources.as_ref().is_none_or(|slots| slots[i] == slots[slot])
+                && constraints
+                    .as_ref()
+                    .is_none_or(|slots| slots[i] == slots[slot])
+        };
+        // Only with a letter budget could a word overlap with itself, which
+        // doesn't make it any less the same word.
+        match (fixed..slot).rev().find(|&i| same_slot(i)) {
+            Some(i) => next > state[i],
+            None => !state.contains(&next),
+        }
+    }
+
+    /// Returns whether the word at index `next` has the same pattern of vowels
+    /// and consonants as the words in the state, if it has to.
+    fn has_pattern_of(&self, state: &[u32], next: u32) -> bool {
+        let first = match state.first() {
+            Some(&first) if self.options.same_pattern => self.word(first).original_word(),
+            _ => return true,
+        };
+        let is_vowel = |c: u8| b"aeiou".contains(&c);
+        let next = self.word(next).original_word();
+        first.len() == next.len()
+            && first
+                .bytes()
+                .zip(next.bytes())
+                .all(|(a, b)| is_vowel(a) == is_vowel(b))
+    }
+
+    /// Returns whether the word at index `next` starts with another letter than
+    /// the words in the state, if it has to.
+    fn has_first_letter_apart_from(&self, state: &[u32], next: u32) -> bool {
+        let first_letter = |i: u32| self.word(i).original_word().bytes().next();
+        !self.options.distinct_first_letters
+            || state.iter().all(|&i| first_letter(i) != first_letter(next))
+    }
+
+    /// Returns whether the word at index `next` forms a banned pair with any
+    /// of the words in the state.
+    fn is_banned_with(&self, state: &[u32], next: u32) -> bool {
+        let banned = &self.options.banned_pairs;
+        !banned.is_empty()
+            && state
+                .iter()
+                .any(|&i| banned.contains(&(i.min(next), i.max(next))))
+    }
+
+    /// Returns how a state of `len` words that cover the letters in `mask`
+    /// compares to what the search is looking for, which is either a number
+    /// of words or a number of letters. A search for maximal sets always
+    /// needs more words, until there are no candidates left.
+    fn progress_of(&self, len: usize, mask: u32) -> Goal {
+        if self.options.maximal {
+            return Goal::Ahead;
+        }
+        let (have, want) = match self.options.letters_covered {
+            // No set of words can cover more letters than aren't excluded.
+            Some(letters) if letters > self.alphabet() => return Goal::Passed,
+            Some(letters) => (mask.count_ones() as usize, letters as usize),
+            None => (len, self.sequence_length),
+        };
+        match have.cmp(&want) {
+            std::cmp::Ordering::Less => Goal::Ahead,
+            std::cmp::Ordering::Equal => Goal::Reached,
+            std::cmp::Ordering::Greater => Goal::Passed,
+        }
+    }
+
+    /// Does the same as `get_disjoint_indices_partial`, but as long as fewer
+    /// than `max_depth_parallel` words have been chosen after the fixed ones,
+    /// it searches what follows each next word in a task of its own. The
+    /// results are still in the same order.
+    pub(crate) fn get_disjoint_indices_parallel(
+        &self,
+        state: Vec<u32>,
+        fixed: usize,
+        valid_indices: &[u32],
+    ) -> Vec<Vec<u32>> {
+        let mask = state.iter().fold(0, |mask, &i| mask | self.word(i).bits);
+        // Only one task can tell which combination comes first.
+        if state.len() - fixed >= self.options.max_depth_parallel
+            || self.options.one_per_start
+            || !matches!(self.progress_of(state.len(), mask), Goal::Ahead)
+        {
+            return self.get_disjoint_indices_partial(state, fixed, valid_indices);
+        }
+
+        let new_valid_indices = self.next_candidates(&state, mask, valid_indices);
+        if new_valid_indices.is_empty() {
+            return self.get_disjoint_indices_partial(state, fixed, valid_indices);
+        }
+        let next: Vec<u32> = new_valid_indices
+            .iter()
+            .copied()
+            .filter(|&next_index| self.may_follow(&state, fixed, next_index))
+            .collect();
+        self.add_to_tree(TreeSize {
+            nodes: 1,
+            leaves: next.is_empty() as u64,
+        });
+        next.par_iter()
+            .map(|&next_index| {
+                let mut state = state.clone();
+                state.push(next_index);
+                self.get_disjoint_indices_parallel(state, fixed, &new_valid_indices)
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Returns every combination that starts with the state, where the words
+    /// that may follow the last word of the state are among `valid_indices`,
+    /// or only the first one with `one_per_start`.
+    pub(crate) fn get_disjoint_indices_partial(
+        &self,
+        mut state: Vec<u32>,
+        fixed: usize,
+        valid_indices: &[u32],
+    ) -> Vec<Vec<u32>> {
+        let mut partial = vec![];
+        let _ = self.visit_partial(&mut state, fixed, valid_indices, &mut |sequence| {
+            info!(words = %self.names(sequence), "found a combination");
+            partial.push(sequence.to_vec());
+            match self.options.one_per_start {
+                true => ControlFlow::Break(()),
+                false => ControlFlow::Continue(()),
+            }
+        });
+        partial
+    }
+
+    /// Calls `visit` with every combination that starts with the state, where
+    /// the words that may follow the last word of the state are among
+    /// `valid_indices`, until `visit` breaks, which this then returns. The
+    /// state is the same again when this returns.
+    pub(crate) fn visit_partial<F: FnMut(&[u32]) -> ControlFlow<()>>(
+        &self,
+        state: &mut Vec<u32>,
+        fixed: usize,
+        valid_indices: &[u32],
+        visit: &mut F,
+    ) -> ControlFlow<()> {
+        let (complete, traversal) = self.begin(state, fixed, valid_indices);
+        if complete {
+            return visit(state);
+        }
+        let mut traversal = match traversal {
+            Some(traversal) => traversal,
+            None => return ControlFlow::Continue(()),
+        };
+        let len = state.len();
+        let flow = self.resume(&mut traversal, state, visit);
+        state.truncate(len);
+        flow
+    }
+
+    /// Sets up the traversal of the combinations that start with the state,
+    /// as described for `visit_partial`. Returns whether the state itself is
+    /// a combination, and otherwise the traversal of the longer ones, if the
+    /// state may still get longer.
+    pub(crate) fn begin(
+        &self,
+        state: &[u32],
+        fixed: usize,
+        valid_indices: &[u32],
+    ) -> (bool, Option<Traversal>) {
+        let mask = state.iter().fold(0, |mask, &i| mask | self.word(i).bits);
+        let leaf = TreeSize {
+            nodes: 1,
+            leaves: 1,
+        };
+        match self.progress_of(state.len(), mask) {
+            Goal::Reached => {
+                self.add_to_tree(leaf);
+                return (true, None);
+            }
+            Goal::Passed => {
+                self.add_to_tree(leaf);
+                return (false, None);
+            }
+            Goal::Ahead => {}
+        }
+
+        let candidates = self.next_candidates(state, mask, valid_indices);
+        if self.options.debug_invariants {
+            self.check_candidates(state, fixed, mask, valid_indices, &candidates);
+        }
+        if self.options.maximal && candidates.is_empty() {
+            self.add_to_tree(leaf);
+            return (true, None);
+        }
+        // Whether this is a leaf is up to the traversal.
+        self.add_to_tree(TreeSize {
+            nodes: 1,
+            leaves: 0,
+        });
+        let traversal = Traversal {
+            fixed,
+            frames: vec![Frame {
+                candidates,
+                cursor: 0,
+                mask,
+                branched: false,
+            }],
+        };
+        (false, Some(traversal))
+    }
+
+    /// Carries on with the traversal from where it was, calling `visit` with
+    /// every combination it reaches until `visit` breaks, which this then
+    /// returns. The traversal can be resumed after that, and goes on after the
+    /// combination that `visit` broke on. The state has to be the one that
+    /// the traversal started from, with the words that it added since, which
+    /// is what the traversal leaves it as when it returns.
+    ///
+    /// The search is a depth-first traversal with an explicit stack rather
+    /// than recursion, so that long combinations don't take a stack frame per
+    /// word, and so that it can stop and resume. Each frame holds the
+    /// candidates for the next word, which are the candidates of the frame
+    /// before it that are disjoint with the word that was added to the state
+    /// in between, so every combination that the traversal reaches is
+    /// guaranteed to be pairwise disjoint, or within the letter budget if
+    /// there is one.
+    pub(crate) fn resume<F: FnMut(&[u32]) -> ControlFlow<()>>(
+        &self,
+        traversal: &mut Traversal,
+        state: &mut Vec<u32>,
+        visit: &mut F,
+    ) -> ControlFlow<()> {
+        let mut tree = TreeSize::default();
+        let flow = self.resume_counting(traversal, state, visit, &mut tree);
+        self.add_to_tree(tree);
+        flow
+    }
+
+    /// Does the same as `resume`, and counts the nodes and leaves it goes
+    /// through in `tree`.
+    fn resume_counting<F: FnMut(&[u32]) -> ControlFlow<()>>(
+        &self,
+        traversal: &mut Traversal,
+        state: &mut Vec<u32>,
+        visit: &mut F,
+        tree: &mut TreeSize,
+    ) -> ControlFlow<()> {
+        let fixed = traversal.fixed;
+        let frames = &mut traversal.frames;
+        while let Some(frame) = frames.last_mut() {
+            let next_index = match frame.candidates.get(frame.cursor) {
+                Some(&next_index) => next_index,
+                None => {
+                    // All the candidates have been tried, so the word that
+                    // led to this frame is done as well, unless this is the
+                    // frame for the state that the traversal started from.
+                    if !frame.branched {
+                        tree.leaves += 1;
+                    }
+                    frames.pop();
+                    if !frames.is_empty() {
+                        state.pop();
+                    }
+                    continue;
+                }
+            };
+            frame.cursor += 1;
+
+            // This check is here because otherwise there would be duplicate
+            // results.
+            if !self.may_follow(state, fixed, next_index) {
+                continue;
+            }
+            frame.branched = true;
+            state.push(next_index);
+            tree.nodes += 1;
+            let mask = frame.mask | self.word(next_index).bits;
+            match self.progress_of(state.len(), mask) {
+                Goal::Reached => {
+                    tree.leaves += 1;
+                    let flow = visit(state);
+                    state.pop();
+                    if flow.is_break() {
+                        return flow;
+                    }
+                    continue;
+                }
+                Goal::Passed => {
+                    tree.leaves += 1;
+                    state.pop();
+                    continue;
+                }
+                Goal::Ahead => {}
+            }
+            let candidates = self.next_candidates(state, mask, &frame.candidates);
+            if self.options.debug_invariants {
+                self.check_candidates(state, fixed, mask, &frame.candidates, &candidates);
+            }
+            // The candidates are all the words that are disjoint with the
+            // state, wherever they are in the list, so if there are none, no
+            // word can be added to it at all.
+            if self.options.maximal && candidates.is_empty() {
+                tree.leaves += 1;
+                let flow = visit(state);
+                state.pop();
+                if flow.is_break() {
+                    return flow;
+                }
+                continue;
+            }
+            frames.push(Frame {
+                candidates,
+                cursor: 0,
+                mask,
+                branched: false,
+            });
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Where a traversal of `Search::resume` is: a frame for the state it started
+/// from and one for each word it added to it since.
+pub(crate) struct Traversal {
+    fixed: usize,
+    frames: Vec<Frame>,
+}
+
+/// A level of a `Traversal`: the words that may be added to the state at that
+/// level, how many of them have been tried, the letters that the state covers
+/// at that level, and whether any word was added to it.
+struct Frame {
+    candidates: Vec<u32>,
+    cursor: usize,
+    mask: u32,
+    branched: bool,
+}
+
+/// Where a state stands with respect to what the search is looking for.
+enum Goal {
+    /// The state needs more words
+    Ahead,
+
+    /// The state is a combination that the search is looking for
+    Reached,
+
+    /// The state went past what the search is looking for, so neither it nor
+    /// anything that starts with it is
+    Passed,
+}
+
+/// Estimates the number of bytes that the adjacency lists for the word list
+/// would take, by counting the partners of a sample of the words.
+fn estimate_adjacency_size(word_list: &[Word]) -> u64 {
+    let len = word_list.len();
+    if len == 0 {
+        return 0;
+    }
+    let step = (len / ADJACENCY_SAMPLE_SIZE).max(1);
+    let sample: Vec<usize> = (0..len).step_by(step).collect();
+    let partners: usize = sample
+        .iter()
+        .map(|&i| {
+            word_list
+                .iter()
+                .filter(|w| word_list[i].is_disjoint_with(w))
+                .count()
+        })
+        .sum();
+    let average_partners = partners as f64 / sample.len() as f64;
+    let per_word = std::mem::size_of::<Vec<u32>>() as f64
+        + average_partners * std::mem::size_of::<u32>() as f64;
+    (per_word * len as f64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_words_from_lists, SlotConstraint};
+
+    /// Returns two word lists with, among other words, the words of a few
+    /// shuffled alphabets cut into five-letter pieces, so that there are
+    /// combinations of up to five words, some of which share words.
+    fn word_lists() -> [String; 2] {
+        let mut seed = 0x2545_f491_u64;
+        let mut next = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize
+        };
+        let mut lists = [String::new(), String::new()];
+        for round in 0..9 {
+            let mut alphabet: Vec<u8> = (b'a'..=b'z').collect();
+            for i in (1..alphabet.len()).rev() {
+                alphabet.swap(i, next() % (i + 1));
+            }
+            for (piece, word) in alphabet.chunks(5).filter(|w| w.len() == 5).enumerate() {
+                let word = std::str::from_utf8(word).unwrap();
+                // Some words are in both lists, so that slots can pick them
+                // from either.
+                let list = (round + piece) % 2;
+                lists[list].push_str(word);
+                lists[list].push('\n');
+                if piece == 0 {
+                    lists[1 - list].push_str(word);
+                    lists[1 - list].push('\n');
+                }
+            }
+        }
+        lists
+    }
+
+    fn read(options: &Options) -> Vec<Word> {
+        let lists = word_lists();
+        get_words_from_lists(lists.iter().map(|list| list.as_bytes()), options).unwrap()
+    }
+
+    /// Finds every combination by trying every sequence of distinct words,
+    /// with the same rules for slots as `Search::is_in_order`, but without
+    /// any pruning other than skipping words that overlap.
+    fn brute_force(
+        word_list: &[Word],
+        options: &Options,
+        constraints: &Constraints,
+        sequence_length: usize,
+    ) -> Vec<Vec<u32>> {
+        fn extend(
+            word_list: &[Word],
+            options: &Options,
+            constraints: &Constraints,
+            sequence_length: usize,
+            state: &mut Vec<u32>,
+            mask: u32,
+            found: &mut Vec<Vec<u32>>,
+        ) {
+            if state.len() == sequence_length {
+                found.push(state.clone());
+                return;
+            }
+            let slot = state.len();
+            let fixed = constraints.required.len();
+            let unslotted = options.slot_sources.is_none() && options.slot_constraints.is_none();
+            for next in 0..word_list.len() as u32 {
+                let word = &word_list[next as usize];
+                if word.mask & (mask | constraints.excluded_letters) != 0 {
+                    continue;
+                }
+                let in_order = if slot < fixed {
+                    next == constraints.required[slot]
+                } else if unslotted {
+                    slot == fixed || next > state[slot - 1]
+                } else {
+                    let allows = |i: usize| {
+                        options
+                            .slot_sources
+                            .as_ref()
+                            .is_none_or(|slots| word.sources & slots[i] != 0)
+                            && options
+                                .slot_constraints
+                                .as_ref()
+                                .is_none_or(|slots| slots[i].allows(word))
+                    };
+                    let same_slot = |i: usize| {
+                        options
+                            .slot_sources
+                            .as_ref()
+                            .is_none_or(|slots| slots[i] == slots[slot])
+                            && options
+                                .slot_constraints
+                                .as_ref()
+                                .is_none_or(|slots| slots[i] == slots[slot])
+                    };
+                    allows(slot)
+                        && (fixed..slot)
+                            .filter(|&i| same_slot(i))
+                            .all(|i| next > state[i])
+                };
+                if !in_order {
+                    continue;
+                }
+                state.push(next);
+                extend(
+                    word_list,
+                    options,
+                    constraints,
+                    sequence_length,
+                    state,
+                    mask | word.mask,
+                    found,
+                );
+                state.pop();
+            }
+        }
+
+        let mut found = vec![];
+        extend(
+            word_list,
+            options,
+            constraints,
+            sequence_length,
+            &mut vec![],
+            0,
+            &mut found,
+        );
+        found
+    }
+
+    fn assert_same_as_brute_force(options: &Options, constraints: &Constraints) {
+        let word_list = read(options);
+        for sequence_length in 2..=5 {
+            let search = Search::new(&word_list, sequence_length, options);
+            let mut found = search.find(constraints, &NoProgress);
+            let mut expected = brute_force(&word_list, options, constraints, sequence_length);
+            assert!(!expected.is_empty(), "{} words", sequence_length);
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected, "{} words", sequence_length);
+        }
+    }
+
+    #[test]
+    fn find_is_the_same_as_brute_force() {
+        assert_same_as_brute_force(&Options::default(), &Constraints::default());
+    }
+
+    #[test]
+    fn find_from_pairs_is_the_same_as_brute_force() {
+        let options = Options {
+            strategy: Strategy::Pairs,
+            ..Options::default()
+        };
+        assert_same_as_brute_force(&options, &Constraints::default());
+    }
+
+    #[test]
+    fn find_without_adjacency_lists_is_the_same_as_brute_force() {
+        let options = Options {
+            max_memory: Some(0),
+            ..Options::default()
+        };
+        assert_same_as_brute_force(&options, &Constraints::default());
+    }
+
+    #[test]
+    fn find_in_parallel_is_the_same_as_find_on_one_task() {
+        let word_list = read(&Options::default());
+        for sequence_length in 2..=5 {
+            let one_task = Options {
+                max_depth_parallel: 0,
+                ..Options::default()
+            };
+            let deep = Options {
+                max_depth_parallel: 3,
+                ..Options::default()
+            };
+            let one_task = Search::new(&word_list, sequence_length, &one_task)
+                .find(&Constraints::default(), &NoProgress);
+            let deep = Search::new(&word_list, sequence_length, &deep)
+                .find(&Constraints::default(), &NoProgress);
+            assert_eq!(one_task, deep, "{} words", sequence_length);
+        }
+    }
+
+    #[test]
+    fn find_with_slot_sources_is_the_same_as_brute_force() {
+        let options = Options {
+            slot_sources: Some(vec![0b01, 0b10, 0b11, 0b11, 0b01]),
+            ..Options::default()
+        };
+        assert_same_as_brute_force(&options, &Constraints::default());
+    }
+
+    #[test]
+    fn find_with_slot_constraints_is_the_same_as_brute_force() {
+        let vowels = [b'a', b'e', b'i', b'o', b'u']
+            .iter()
+            .fold(0, |mask, c| mask | 1 << (c - b'a'));
+        let options = Options {
+            slot_constraints: Some(vec![
+                SlotConstraint {
+                    some_of: Some(vowels),
+                    ..SlotConstraint::default()
+                },
+                SlotConstraint::default(),
+                SlotConstraint {
+                    none_of: 1 << (b'e' - b'a'),
+                    ..SlotConstraint::default()
+                },
+                SlotConstraint::default(),
+                SlotConstraint::default(),
+            ]),
+            ..Options::default()
+        };
+        assert_same_as_brute_force(&options, &Constraints::default());
+    }
+
+    #[test]
+    fn find_with_constraints_is_the_same_as_brute_force() {
+        // Excluding the letter that a combination of five words leaves out, and
+        // requiring one of its words, still leaves that combination.
+        let options = Options::default();
+        let word_list = read(&options);
+        let combination = &brute_force(&word_list, &options, &Constraints::default(), 5)[3];
+        let covered = combination
+            .iter()
+            .fold(0, |mask, &i| mask | word_list[i as usize].mask);
+        let constraints = Constraints {
+            required: vec![combination[1]],
+            excluded_letters: !covered & ((1 << 26) - 1),
+        };
+        assert_same_as_brute_force(&options, &constraints);
+    }
+
+    fn letters(letters: &str) -> u32 {
+        letters.bytes().fold(0, |mask, c| mask | 1 << (c - b'a'))
+    }
+
+    #[test]
+    fn find_with_excluded_letters_is_the_same_as_brute_force() {
+        let excluded = letters("jqz");
+        let options = Options {
+            excluded_letters: excluded,
+            ..Options::default()
+        };
+        let word_list = read(&options);
+        for word in &word_list {
+            assert_eq!(word.mask() & excluded, 0, "{:?}", word);
+            assert_eq!(word.bits, dense_letters(word.mask(), excluded));
+            assert!(
+                word.bits < 1 << 23,
+                "{:?} has the bits {:#x}",
+                word,
+                word.bits
+            );
+        }
+        for (i, word) in word_list.iter().enumerate() {
+            for other in &word_list[i + 1..] {
+                assert_eq!(
+                    word.is_disjoint_with(other),
+                    word.mask() & other.mask() == 0,
+                    "{:?} and {:?}",
+                    word,
+                    other
+                );
+            }
+        }
+
+        // The letters that the constraints exclude are real letters as well.
+        let constraints = Constraints {
+            required: vec![],
+            excluded_letters: letters("e"),
+        };
+        for constraints in [Constraints::default(), constraints] {
+            for sequence_length in 2..=4 {
+                let search = Search::new(&word_list, sequence_length, &options);
+                let mut found = search.find(&constraints, &NoProgress);
+                let mut expected = brute_force(&word_list, &options, &constraints, sequence_length);
+                assert!(
+                    sequence_length == 4 || !expected.is_empty(),
+                    "{} words",
+                    sequence_length
+                );
+                found.sort();
+                expected.sort();
+                assert_eq!(found, expected, "{} words", sequence_length);
+            }
+        }
+
+        // The unused letters are counted by the real letters, so every
+        // combination leaves the excluded ones unused.
+        let combinations = brute_force(&word_list, &options, &Constraints::default(), 3);
+        let mut expected = [0; 26];
+        for combination in &combinations {
+            let mask = combination
+                .iter()
+                .fold(0, |mask, &i| mask | word_list[i as usize].mask());
+            for (letter, count) in expected.iter_mut().enumerate() {
+                if mask & 1 << letter == 0 {
+                    *count += 1;
+                }
+            }
+        }
+        let (counts, len) = crate::count_by_unused_letter(&word_list, 3, &options, &NoProgress);
+        assert_eq!(counts, expected);
+        assert_eq!(len, combinations.len() as u64);
+        for letter in [b'j', b'q', b'z'] {
+            assert_eq!(counts[(letter - b'a') as usize], combinations.len() as u64);
+        }
+    }
+
+    #[test]
+    fn sets_of_more_letters_than_are_left_are_not_searched_for() {
+        let options = Options {
+            excluded_letters: letters("jqz"),
+            letters_covered: Some(24),
+            ..Options::default()
+        };
+        let word_list = read(&options);
+        let search = Search::new(&word_list, 5, &options);
+        assert!(search.find(&Constraints::default(), &NoProgress).is_empty());
+        assert_eq!(search.tree_size().nodes, 0);
+
+        // One letter less is searched for as usual.
+        let options = Options {
+            letters_covered: Some(23),
+            ..options
+        };
+        let search = Search::new(&word_list, 5, &options);
+        search.find(&Constraints::default(), &NoProgress);
+        assert!(search.tree_size().nodes > 0);
+    }
+
+    #[test]
+    fn tree_is_the_same_from_pairs() {
+        let word_list = read(&Options::default());
+        for sequence_length in 2..=5 {
+            let tree_size = |strategy| {
+                let options = Options {
+                    strategy,
+                    ..Options::default()
+                };
+                let search = Search::new(&word_list, sequence_length, &options);
+                search.find(&Constraints::default(), &NoProgress);
+                search.tree_size()
+            };
+            let words = tree_size(Strategy::Words);
+            let pairs = tree_size(Strategy::Pairs);
+            assert_eq!(words, pairs, "{} words", sequence_length);
+        }
+    }
+
+    #[test]
+    fn cursor_is_the_same_as_find_in_batches_of_any_size() {
+        let word_list = read(&Options::default());
+        let plain = Options {
+            strategy: Strategy::Words,
+            ..Options::default()
+        };
+        let maximal = Options {
+            maximal: true,
+            ..plain.clone()
+        };
+        let one_per_start = Options {
+            one_per_start: true,
+            ..plain.clone()
+        };
+        for (name, options) in [
+            ("plain", plain),
+            ("maximal", maximal),
+            ("one per start", one_per_start),
+        ] {
+            for sequence_length in 2..=5 {
+                let search = Search::new(&word_list, sequence_length, &options);
+                let expected = search.find(&Constraints::default(), &NoProgress);
+                for n in [1, 3, 1000] {
+                    let mut cursor = search.cursor();
+                    let mut batches = vec![];
+                    while !cursor.is_done() {
+                        let batch = cursor.next_batch(n);
+                        assert!(batch.len() <= n);
+                        batches.push(batch);
+                    }
+                    assert!(cursor.next_batch(n).is_empty());
+                    let found: Vec<Vec<u32>> = batches.concat();
+                    let what = format!("{}, {} words, n = {}", name, sequence_length, n);
+                    assert_eq!(found, expected, "{}", what);
+                    // With one combination per batch, a starting word with
+                    // more than one combination ends a batch in the middle of
+                    // its traversal, which the next batch goes on from.
+                    let same_start = |pair: &[Vec<u32>]| pair[0][0] == pair[1][0];
+                    if n == 1 && expected.windows(2).any(same_start) {
+                        let mid_traversal = batches
+                            .windows(2)
+                            .filter(|pair| !pair[0].is_empty() && !pair[1].is_empty())
+                            .any(|pair| pair[0][0][0] == pair[1][0][0]);
+                        assert!(mid_traversal, "{}", what);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_with_distinct_first_letters_leaves_out_the_others() {
+        // Only words with letters in common can start with the same letter.
+        let options = Options {
+            letter_budget: Some(2),
+            ..Options::default()
+        };
+        let word_list = read(&options);
+        let first_letter = |i: &u32| word_list[*i as usize].original_word().as_bytes()[0];
+        let all = Search::new(&word_list, 3, &options).find(&Constraints::default(), &NoProgress);
+        let expected: Vec<Vec<u32>> = all
+            .iter()
+            .filter(|sequence| {
+                let letters: HashSet<u8> = sequence.iter().map(first_letter).collect();
+                letters.len() == sequence.len()
+            })
+            .cloned()
+            .collect();
+        assert!(!expected.is_empty() && expected.len() < all.len());
+
+        let options = Options {
+            distinct_first_letters: true,
+            ..options
+        };
+        let found = Search::new(&word_list, 3, &options).find(&Constraints::default(), &NoProgress);
+        assert_eq!(found, expected);
+    }
+}
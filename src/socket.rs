@@ -0,0 +1,169 @@
+//! Streaming the combinations to a consumer that runs at the same time, over a
+//! Unix domain socket or a named pipe, as the search finds them.
+
+use crate::output::{write_sequence, Columns};
+use five_words::{ProgressSink, Word};
+use std::fs::OpenOptions;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// What to do when the consumer goes away before the search is done.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OnDisconnect {
+    /// Skip the rest of the search
+    Stop,
+    /// Finish the search anyway, without writing anything more
+    Continue,
+}
+
+/// A `ProgressSink` that passes every update on, and that writes the
+/// combinations that the search found from each word to the consumer as soon
+/// as the search is done with that word.
+pub struct SocketOutput<'a> {
+    word_list: &'a [Word],
+    columns: &'a Columns,
+    progress: &'a dyn ProgressSink,
+    on_disconnect: OnDisconnect,
+    stopped: AtomicBool,
+    state: Mutex<State>,
+}
+
+/// What the threads of the search share: the connection, the number of
+/// combinations written to it, and the first error that writing ran into,
+/// after which nothing more is written.
+struct State {
+    out: BufWriter<Box<dyn Write + Send>>,
+    written: usize,
+    error: Option<std::io::Error>,
+    /// The socket that was created for the consumer to connect to, which is
+    /// removed again when the search is done.
+    created: Option<PathBuf>,
+}
+
+impl<'a> SocketOutput<'a> {
+    /// Connects to the socket or opens the named pipe at `path`. If there is
+    /// nothing there yet, this creates a socket and waits for a consumer to
+    /// connect to it.
+    pub fn new(
+        path: &Path,
+        on_disconnect: OnDisconnect,
+        word_list: &'a [Word],
+        columns: &'a Columns,
+        progress: &'a dyn ProgressSink,
+    ) -> std::io::Result<Self> {
+        let (out, created) = connect(path)?;
+        Ok(SocketOutput {
+            word_list,
+            columns,
+            progress,
+            on_disconnect,
+            stopped: AtomicBool::new(false),
+            state: Mutex::new(State {
+                out: BufWriter::new(out),
+                written: 0,
+                error: None,
+                created,
+            }),
+        })
+    }
+
+    /// Flushes what hasn't been written yet, and returns the first error that
+    /// writing ran into, if any. The consumer disconnecting isn't an error.
+    pub fn finish(self) -> std::io::Result<()> {
+        let mut state = self.state.into_inner().unwrap();
+        if state.error.is_none() {
+            state.error = state.out.flush().err();
+        }
+        if let Some(path) = &state.created {
+            std::fs::remove_file(path)?;
+        }
+        match state.error {
+            Some(e) if !is_disconnect(&e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> std::io::Result<(Box<dyn Write + Send>, Option<PathBuf>)> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            Ok((Box::new(UnixStream::connect(path)?), None))
+        }
+        Ok(_) => Ok((Box::new(OpenOptions::new().write(true).open(path)?), None)),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let listener = UnixListener::bind(path)?;
+            eprintln!("Waiting for a consumer to connect to {}.", path.display());
+            let (stream, _) = listener.accept()?;
+            Ok((Box::new(stream), Some(path.to_path_buf())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Without Unix domain sockets, a named pipe is opened like any other file.
+#[cfg(not(unix))]
+fn connect(path: &Path) -> std::io::Result<(Box<dyn Write + Send>, Option<PathBuf>)> {
+    Ok((Box::new(OpenOptions::new().write(true).open(path)?), None))
+}
+
+/// Returns whether the error means that the consumer went away.
+fn is_disconnect(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+    )
+}
+
+impl ProgressSink for SocketOutput<'_> {
+    forward_progress!(
+        progress;
+        set_total,
+        tasks_are_pairs,
+        advance,
+        set_found,
+        set_current,
+        finish_task,
+    );
+
+    fn is_cancelled(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed) || self.progress.is_cancelled()
+    }
+
+    fn finish_start(&self, start: u32, combinations: &[Vec<u32>]) {
+        self.progress.finish_start(start, combinations);
+        if combinations.is_empty() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if state.error.is_some() {
+            return;
+        }
+        let State { out, written, .. } = &mut *state;
+        let result = combinations
+            .iter()
+            .try_for_each(|combination| {
+                *written += 1;
+                write_sequence(out, self.columns, *written, combination, self.word_list)
+            })
+            .and_then(|_| out.flush());
+        if let Err(e) = result {
+            if is_disconnect(&e) {
+                warn!("the consumer disconnected from the output socket");
+                if self.on_disconnect == OnDisconnect::Stop {
+                    self.stopped.store(true, Ordering::Relaxed);
+                }
+            } else {
+                self.stopped.store(true, Ordering::Relaxed);
+            }
+            state.error = Some(e);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Turning the indices that a search returns back into words.
+
+use crate::Word;
+
+/// The combinations that a search found, along with the word list that their
+/// indices point into. Iterating over it gives the words of each combination
+/// as they appeared in the input.
+///
+/// ```
+/// use five_words::{get_disjoint_indices, get_words, NoProgress, Options, Solutions};
+///
+/// let options = Options::default();
+/// let input = "fjord\ngucks\nnymph\nvibex\nwaltz\nhello\n";
+/// let word_list = get_words(input.as_bytes(), &options).unwrap();
+/// let sequences = get_disjoint_indices(&word_list, 5, &options, &NoProgress);
+/// for solution in Solutions::new(&word_list, sequences) {
+///     assert_eq!(solution, ["fjord", "gucks", "nymph", "vibex", "waltz"]);
+/// }
+/// ```
+pub struct Solutions<'a> {
+    word_list: &'a [Word],
+    sequences: Vec<Vec<u32>>,
+}
+
+impl<'a> Solutions<'a> {
+    /// Wraps the combinations, which have to be indices into `word_list`.
+    pub fn new(word_list: &'a [Word], sequences: Vec<Vec<u32>>) -> Self {
+        Solutions {
+            word_list,
+            sequences,
+        }
+    }
+
+    /// Returns the number of combinations.
+    pub fn len(&self) -> usize {
+        self.sequences.len()
+    }
+
+    /// Returns `true` if there are no combinations.
+    pub fn is_empty(&self) -> bool {
+        self.sequences.is_empty()
+    }
+
+    /// Returns the combinations as indices into the word list.
+    pub fn indices(&self) -> &[Vec<u32>] {
+        &self.sequences
+    }
+}
+
+impl<'a> IntoIterator for Solutions<'a> {
+    type Item = Vec<&'a str>;
+    type IntoIter = IntoIter<'a>;
+
+    fn into_iter(self) -> IntoIter<'a> {
+        IntoIter {
+            word_list: self.word_list,
+            sequences: self.sequences.into_iter(),
+        }
+    }
+}
+
+/// An iterator over the words of each combination, in the order the search
+/// found them in.
+pub struct IntoIter<'a> {
+    word_list: &'a [Word],
+    sequences: std::vec::IntoIter<Vec<u32>>,
+}
+
+impl<'a> Iterator for IntoIter<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Vec<&'a str>> {
+        let sequence = self.sequences.next()?;
+        Some(
+            sequence
+                .iter()
+                .map(|&i| self.word_list[i as usize].original_word())
+                .collect(),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sequences.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IntoIter<'_> {}
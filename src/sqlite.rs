@@ -0,0 +1,79 @@
+//! Writing the combinations that were found to an SQLite database. The rows
+//! are written once the search is done, like every other format does, rather
+//! than as the combinations are found.
+
+use five_words::Word;
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// The number of combinations that are inserted per transaction.
+const BATCH_SIZE: usize = 10_000;
+
+/// Writes the word list and the combinations to a new SQLite database at the
+/// given path. The words are stored with their index in the word list as their
+/// ID, and each combination is stored as one row per word in
+/// `solution_words`, so that combinations of any length fit the same schema.
+/// The combinations are inserted in transactions of `BATCH_SIZE` each.
+pub fn write_database(
+    path: &Path,
+    word_list: &[Word],
+    sequences: &[Vec<u32>],
+) -> std::io::Result<()> {
+    write_database_inner(path, word_list, sequences).map_err(std::io::Error::other)
+}
+
+fn write_database_inner(
+    path: &Path,
+    word_list: &[Word],
+    sequences: &[Vec<u32>],
+) -> rusqlite::Result<()> {
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(
+        "DROP TABLE IF EXISTS solution_words;
+         DROP TABLE IF EXISTS solutions;
+         DROP TABLE IF EXISTS words;
+         CREATE TABLE words (
+             id INTEGER PRIMARY KEY,
+             word TEXT NOT NULL
+         );
+         CREATE TABLE solutions (
+             id INTEGER PRIMARY KEY
+         );
+         CREATE TABLE solution_words (
+             solution_id INTEGER NOT NULL REFERENCES solutions (id),
+             position INTEGER NOT NULL,
+             word_id INTEGER NOT NULL REFERENCES words (id),
+             PRIMARY KEY (solution_id, position)
+         );",
+    )?;
+
+    let transaction = connection.transaction()?;
+    {
+        let mut insert_word =
+            transaction.prepare("INSERT INTO words (id, word) VALUES (?1, ?2)")?;
+        for (id, word) in word_list.iter().enumerate() {
+            insert_word.execute(params![id, word.original_word()])?;
+        }
+    }
+    transaction.commit()?;
+
+    for (batch_number, batch) in sequences.chunks(BATCH_SIZE).enumerate() {
+        let transaction = connection.transaction()?;
+        {
+            let mut insert_solution =
+                transaction.prepare_cached("INSERT INTO solutions (id) VALUES (?1)")?;
+            let mut insert_solution_word = transaction.prepare_cached(
+                "INSERT INTO solution_words (solution_id, position, word_id) VALUES (?1, ?2, ?3)",
+            )?;
+            for (i, sequence) in batch.iter().enumerate() {
+                let id = batch_number * BATCH_SIZE + i;
+                insert_solution.execute(params![id])?;
+                for (position, word_id) in sequence.iter().enumerate() {
+                    insert_solution_word.execute(params![id, position, word_id])?;
+                }
+            }
+        }
+        transaction.commit()?;
+    }
+    Ok(())
+}
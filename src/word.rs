@@ -0,0 +1,331 @@
+//! The words that the search is made of.
+
+use core::hash::{Hash, Hasher};
+use std::fmt;
+use std::str::FromStr;
+
+/// A word from the input, along with its letters in sorted order. Anagrams of
+/// each other are equal, unless the word list keeps anagrams.
+pub struct Word {
+    pub(crate) word: Box<[u8]>,
+    pub(crate) mask: u32,
+
+    /// The letters as the search numbers them. This is the same as the mask,
+    /// unless some letters are excluded, in which case the letters that are
+    /// left are numbered densely from bit 0, in the order of the alphabet.
+    pub(crate) bits: u32,
+    pub(crate) original_word: String,
+
+    /// What the `Word` is hashed and compared by, which is the sorted bytes
+    /// unless anagrams are kept, in which case it's the original word.
+    pub(crate) key: Box<[u8]>,
+
+    /// The lists that the word appears in, with bit 0 for the first list.
+    pub(crate) sources: u32,
+
+    /// The other spellings of the same letters that were left out of the word
+    /// list in favor of this one, in the order they were read.
+    pub(crate) anagrams: Vec<String>,
+}
+
+impl Hash for Word {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Only consider the key when hashing the Word, because we're also
+        // storing the original word. By default it's not desirable for that
+        // to be part of the hash, because otherwise we would be storing all
+        // the anagrams of this Word in the set, too.
+        self.key.hash(state);
+    }
+}
+
+impl PartialEq for Word {
+    // Only consider the key when comparing Words, because by default it's
+    // desireable for anagrams of the original words to be equal, not
+    // different.
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Word {}
+
+impl fmt::Debug for Word {
+    /// Shows the spelling, the sorted letters and the mask, as in
+    /// `Word("fjord" [dfjor] 0x00024228)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Word({:?} [{}] {:#010x})",
+            self.original_word,
+            String::from_utf8_lossy(&self.word),
+            self.mask
+        )
+    }
+}
+
+impl fmt::Display for Word {
+    /// Shows the word as it appeared in the input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.original_word)
+    }
+}
+
+impl Word {
+    pub(crate) fn new(word: Box<[u8]>, original_word: String) -> Self {
+        debug_assert!(
+            word.iter().all(|c| c.is_ascii_lowercase()),
+            "{:?} has characters other than lowercase letters",
+            original_word
+        );
+        let mask = word.iter().fold(0, |mask, c| mask | 1 << (c - b'a'));
+        let word = Word {
+            key: word.clone(),
+            word,
+            mask,
+            bits: mask,
+            original_word,
+            sources: 0,
+            anagrams: vec![],
+        };
+        debug_assert!(
+            word.is_valid(),
+            "{:?} is not a valid word",
+            word.original_word
+        );
+        word
+    }
+
+    /// Makes a `Word` out of a string that is already known to consist of
+    /// different lowercase ASCII letters, without sorting it or checking it.
+    /// The sorted letters are read off the mask instead. Anything else than a
+    /// lowercase letter doesn't make it panic, but it does set a bit that
+    /// mostly belongs to some other letter, such as `-` setting the one for
+    /// `m`, so the word that comes out is wrong.
+    pub(crate) fn from_valid(original_word: &str) -> Self {
+        let mask = original_word
+            .bytes()
+            .fold(0, |mask, c| mask | 1 << (c.wrapping_sub(b'a') & 31));
+        Word::new(letters_of(mask).into_bytes().into(), original_word.into())
+    }
+
+    /// Returns `true` if the word has different letters, in sorted order,
+    /// which is what the search relies on. Every `Word` that the library makes
+    /// is valid.
+    pub fn is_valid(&self) -> bool {
+        self.mask.count_ones() as usize == self.word.len()
+            && self.word.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    /// Returns the word as it appeared in the input.
+    pub fn original_word(&self) -> &str {
+        &self.original_word
+    }
+
+    /// Returns the set of lists that the word appears in, with bit 0 for the
+    /// first list that was read.
+    pub fn sources(&self) -> u32 {
+        self.sources
+    }
+
+    /// Returns the spellings of the same letters that were left out of the
+    /// word list in favor of this word, in the order they were read.
+    pub fn anagrams(&self) -> &[String] {
+        &self.anagrams
+    }
+
+    /// Returns the set of letters in the word, with bit 0 for `a` up to bit 25
+    /// for `z`.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Returns where the letter is in the word as it's spelled, starting at 0,
+    /// or `None` if the word doesn't have it. The sorted letters don't say,
+    /// so this goes by the original spelling, which every `Word` keeps anyway.
+    pub fn position_of(&self, letter: u8) -> Option<usize> {
+        self.original_word.bytes().position(|c| c == letter)
+    }
+
+    /// Returns `true` if the two `Word`s do not have any characters in common.
+    pub fn is_disjoint_with(&self, other: &Self) -> bool {
+        self.bits & other.bits == 0
+    }
+
+    /// Numbers the letters of the word that the search goes by densely over
+    /// the letters that aren't excluded. The word must not have any of them.
+    pub(crate) fn renumber(&mut self, excluded_letters: u32) {
+        debug_assert_eq!(self.mask & excluded_letters, 0);
+        self.bits = dense_letters(self.mask, excluded_letters);
+    }
+
+    /// Does the same as `is_disjoint_with`, but by walking the sorted letters
+    /// of both `Word`s instead of comparing their masks. This is how it used to
+    /// be done, and it is kept around to compare the two in the benchmarks.
+    #[allow(clippy::comparison_chain)]
+    pub fn is_disjoint_with_sorted(&self, other: &Self) -> bool {
+        let mut a = 0;
+        let mut b = 0;
+
+        loop {
+            if a == self.word.len() || b == other.word.len() {
+                break;
+            }
+            if self.word[a] == other.word[b] {
+                return false;
+            } else if self.word[a] < other.word[b] {
+                a += 1;
+            } else {
+                b += 1;
+            }
+        }
+        true
+    }
+}
+
+/// The reasons why a string can't be parsed into a `Word`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWordError {
+    /// The string doesn't have any characters
+    Empty,
+
+    /// The string has a character that isn't an ASCII letter
+    NotALetter(char),
+
+    /// The string has this letter more than once
+    RepeatedLetter(char),
+}
+
+impl fmt::Display for ParseWordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWordError::Empty => write!(f, "a word needs at least one letter"),
+            ParseWordError::NotALetter(c) => write!(f, "{:?} is not an ASCII letter", c),
+            ParseWordError::RepeatedLetter(c) => write!(f, "the letter {} is repeated", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseWordError {}
+
+impl FromStr for Word {
+    type Err = ParseWordError;
+
+    /// Parses a string of different ASCII letters into a `Word`, in lowercase.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseWordError::Empty);
+        }
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_alphabetic()) {
+            return Err(ParseWordError::NotALetter(c));
+        }
+        let original_word = s.to_ascii_lowercase();
+        let mut bytes: Box<[u8]> = original_word.as_bytes().into();
+        bytes.sort();
+        if let Some(pair) = bytes.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(ParseWordError::RepeatedLetter(pair[0] as char));
+        }
+        Ok(Word::new(bytes, original_word))
+    }
+}
+
+/// Returns `true` if the array has no duplicate values. This function assumes
+/// that `word` is sorted.
+pub fn all_characters_unique(word: &[u8]) -> bool {
+    for i in 1..word.len() {
+        if word[i - 1] == word[i] {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns the letters in the mask as a string, in alphabetical order.
+pub fn letters_of(mask: u32) -> String {
+    (0..26)
+        .filter(|i| mask & 1 << i != 0)
+        .map(|i| (b'a' + i) as char)
+        .collect()
+}
+
+/// Returns the letters in the mask numbered densely over the letters that
+/// aren't excluded, so that the first letter that's left is bit 0, the second
+/// is bit 1, and so on. Excluded letters in the mask are dropped.
+pub(crate) fn dense_letters(mask: u32, excluded_letters: u32) -> u32 {
+    (0..26)
+        .filter(|letter| excluded_letters & 1 << letter == 0)
+        .enumerate()
+        .filter(|(_, letter)| mask & 1 << letter != 0)
+        .fold(0, |dense, (bit, _)| dense | 1 << bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_word() {
+        let word: Word = "fjord".parse().unwrap();
+        assert_eq!(word.original_word(), "fjord");
+        assert_eq!(&*word.word, b"dfjor");
+        assert_eq!(letters_of(word.mask()), "dfjor");
+    }
+
+    #[test]
+    fn parses_a_word_in_lowercase() {
+        let word: Word = "FJord".parse().unwrap();
+        assert_eq!(word.original_word(), "fjord");
+        assert_eq!(word, "fjord".parse().unwrap());
+        assert_eq!(word.mask(), "fjord".parse::<Word>().unwrap().mask());
+    }
+
+    #[test]
+    fn rejects_an_empty_word() {
+        assert_eq!("".parse::<Word>(), Err(ParseWordError::Empty));
+    }
+
+    #[test]
+    fn rejects_a_character_that_isnt_a_letter() {
+        assert_eq!(
+            "fj-rd".parse::<Word>(),
+            Err(ParseWordError::NotALetter('-'))
+        );
+        assert_eq!(
+            "fjörd".parse::<Word>(),
+            Err(ParseWordError::NotALetter('ö'))
+        );
+    }
+
+    #[test]
+    fn rejects_a_repeated_letter() {
+        assert_eq!(
+            "fjordo".parse::<Word>(),
+            Err(ParseWordError::RepeatedLetter('o'))
+        );
+        // Letters that only differ in case are the same letter.
+        assert_eq!(
+            "Fjordf".parse::<Word>(),
+            Err(ParseWordError::RepeatedLetter('f'))
+        );
+    }
+
+    #[test]
+    fn parsed_word_is_valid() {
+        assert!("fjord".parse::<Word>().unwrap().is_valid());
+        assert!(Word::from_valid("nymph").is_valid());
+    }
+
+    #[test]
+    fn word_with_a_repeated_letter_is_not_valid() {
+        // The mask has a bit per letter, so the repeated one is only there once.
+        let mut word: Word = "fjord".parse().unwrap();
+        word.word = (*b"dfjoor").into();
+        assert!(!word.is_valid());
+    }
+
+    #[test]
+    fn word_with_unsorted_letters_is_not_valid() {
+        let mut word: Word = "fjord".parse().unwrap();
+        word.word = (*b"fjord").into();
+        assert!(!word.is_valid());
+    }
+}